@@ -0,0 +1,393 @@
+use crate::DirpError;
+
+use crate::chunk::cast::BitmapInfo;
+
+use crate::chunk::bitd::BitmapData;
+
+use crate::chunk::alfa::AlphaData;
+
+// A decoded bitmap. The pixels are stored row by row from the top left,
+// with four bytes per pixel in the order red, green, blue and alpha.
+pub struct Bitmap {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+    indices: Option<Vec<u8>>,
+    palette: Option<Vec<[u8; 3]>>,
+}
+
+impl Bitmap {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    // The number of bytes from the start of one row to the next.
+    pub fn stride(&self) -> usize {
+        self.width * 4
+    }
+
+    // The RGBA pixels of the bitmap.
+    pub fn pixels(&self) -> &Vec<u8> {
+        &self.pixels
+    }
+
+    // Returns the RGBA pixel at 'x', 'y'.
+    pub fn pixel(&self, x: usize, y: usize) -> Option<[u8; 4]> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+
+        let i = y * self.stride() + x * 4;
+
+        Some([self.pixels[i], self.pixels[i + 1], self.pixels[i + 2], self.pixels[i + 3]])
+    }
+
+    // The palette index of every pixel, one byte per pixel,
+    // for bitmaps with a bit depth of 2, 4 or 8.
+    pub fn indices(&self) -> Option<&Vec<u8>> {
+        self.indices.as_ref()
+    }
+
+    // The colors of the palette that the indices refer to.
+    pub fn palette(&self) -> Option<&Vec<[u8; 3]>> {
+        self.palette.as_ref()
+    }
+}
+
+#[cfg(test)]
+impl Bitmap {
+    pub(crate) fn new(width: usize, height: usize, pixels: Vec<u8>) -> Bitmap {
+        Bitmap {
+            width,
+            height,
+            pixels,
+            indices: None,
+            palette: None,
+        }
+    }
+}
+
+// Decode the BITD data of a bitmap cast member.
+// Bitmaps with a bit depth of 2, 4 or 8 are indices into 'palette'.
+// The alpha channel of a 32-bit bitmap is replaced by 'alpha', if given.
+pub(crate) fn parse_bitmap_data(
+    info: &BitmapInfo,
+    data: &BitmapData,
+    alpha: Option<&AlphaData>,
+    palette: &[[u8; 3]]
+) -> Result<Bitmap, DirpError> {
+    let width = info.width();
+    let height = info.height();
+    let depth = info.depth();
+    let indexed = matches!(depth, 2 | 4 | 8);
+
+    // There are no rows to split the data into
+    if width == 0 || height == 0 {
+        return Ok(Bitmap {
+            width: 0,
+            height: 0,
+            pixels: Vec::new(),
+            indices: if indexed { Some(Vec::new()) } else { None },
+            palette: if indexed { Some(palette.to_vec()) } else { None },
+        });
+    }
+
+    // Director pads the rows of some bitmaps beyond what they need,
+    // which is given by the row bytes of the cast member.
+    let row_bytes = match info.row_bytes() {
+        n if n >= row_bytes(width, depth) => n,
+        _ => row_bytes(width, depth),
+    };
+
+    let data = decompress(data.data(), row_bytes * height)?;
+
+    let mut pixels = Vec::with_capacity(width * height * 4);
+
+    for row in data.chunks(row_bytes).take(height) {
+        for x in 0..width {
+            pixels.extend_from_slice(&pixel(row, x, width, depth, palette));
+        }
+    }
+
+    let indices = if indexed {
+        let indices = data.chunks(row_bytes)
+            .take(height)
+            .flat_map(|row| (0..width).map(move |x| index(row, x, depth)))
+            .collect();
+
+        Some(indices)
+    } else {
+        None
+    };
+
+    if let Some(alpha) = alpha {
+        // The alpha plane has one byte per pixel,
+        // with rows padded like those of the BITD chunk.
+        let alpha_row_bytes = self::row_bytes(width, 8);
+
+        let alpha = decompress(alpha.data(), alpha_row_bytes * height)?;
+
+        for (row, alpha) in pixels.chunks_mut(width * 4).zip(alpha.chunks(alpha_row_bytes)) {
+            for (pixel, &a) in row.chunks_mut(4).zip(alpha) {
+                pixel[3] = a;
+            }
+        }
+    }
+
+    Ok(Bitmap {
+        width,
+        height,
+        pixels,
+        indices,
+        palette: if indexed { Some(palette.to_vec()) } else { None },
+    })
+}
+
+// Whether bitmaps with the bit depth 'depth' can be decoded.
+pub(crate) fn is_supported(depth: usize) -> bool {
+    matches!(depth, 1 | 2 | 4 | 8 | 16 | 32)
+}
+
+// The least number of bytes in a row of the bitmap.
+// Rows are padded to an even number of bytes.
+fn row_bytes(width: usize, depth: usize) -> usize {
+    (width * depth).div_ceil(16) * 2
+}
+
+// Returns the 'len' bytes of uncompressed data in 'data'. Director only
+// compresses the data if it makes it smaller, so data that already is
+// of the right length is used as it is.
+fn decompress(data: &[u8], len: usize) -> Result<Vec<u8>, DirpError> {
+    if data.len() == len {
+        return Ok(data.to_vec());
+    }
+
+    unpack(data, len)
+}
+
+// Decompress the PackBits style run-length encoding used by BITD chunks.
+// Missing data is filled with zeros.
+fn unpack(data: &[u8], len: usize) -> Result<Vec<u8>, DirpError> {
+    let mut unpacked = Vec::with_capacity(len);
+    let mut data = data.iter();
+
+    while unpacked.len() < len {
+        let n = match data.next() {
+            Some(&n) => n,
+            None => break,
+        };
+
+        // NOTE This value is from Shockky. MrBrax use >= 129
+        if n >= 128 {
+            let n = 257 - n as usize;
+
+            let b = match data.next() {
+                Some(&b) => b,
+                None => break,
+            };
+
+            unpacked.extend(std::iter::repeat_n(b, n));
+        } else {
+            let n = n as usize + 1;
+
+            unpacked.extend(data.by_ref().take(n));
+        }
+    }
+
+    if unpacked.len() > len {
+        return Err(DirpError::BitmapOverrun {
+            expected: len,
+            found: unpacked.len(),
+        });
+    }
+
+    unpacked.resize(len, 0);
+    Ok(unpacked)
+}
+
+// Decode pixel 'x' of a row as RGBA.
+fn pixel(row: &[u8], x: usize, width: usize, depth: usize, palette: &[[u8; 3]]) -> [u8; 4] {
+    match depth {
+        // Set bits are black, no matter what the palette is.
+        1 => {
+            let bit = row[x / 8] >> (7 - x % 8) & 1;
+
+            if bit == 1 {
+                [0, 0, 0, 255]
+            } else {
+                [255, 255, 255, 255]
+            }
+        },
+        2 | 4 | 8 => {
+            let index = index(row, x, depth) as usize;
+
+            let [r, g, b] = palette.get(index).copied().unwrap_or([0, 0, 0]);
+
+            [r, g, b, 255]
+        },
+        // The row is split into the high bytes followed by the low
+        // bytes of the pixels, which are 5-5-5 RGB.
+        16 => {
+            let value = (row[x] as u16) << 8 | row[width + x] as u16;
+
+            let r = (value >> 10 & 0x1f) as u8;
+            let g = (value >> 5 & 0x1f) as u8;
+            let b = (value & 0x1f) as u8;
+
+            [r << 3 | r >> 2, g << 3 | g >> 2, b << 3 | b >> 2, 255]
+        },
+        // The row is split into one plane per channel,
+        // in the order alpha, red, green and blue.
+        32 => {
+            [row[width + x], row[2 * width + x], row[3 * width + x], row[x]]
+        },
+        _ => [0, 0, 0, 255],
+    }
+}
+
+// The palette index of pixel 'x' of a row with a bit depth of 2, 4 or 8.
+// The leftmost pixel is in the most significant bits.
+fn index(row: &[u8], x: usize, depth: usize) -> u8 {
+    let per_byte = 8 / depth;
+    let shift = (per_byte - 1 - x % per_byte) * depth;
+
+    (row[x / per_byte] >> shift) & ((1u16 << depth) - 1) as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PALETTE: [[u8; 3]; 4] = [[255, 255, 255], [255, 0, 0], [0, 255, 0], [0, 0, 0]];
+
+    fn decode(width: i16, height: i16, depth: usize, row_bytes: usize, data: &[u8]) -> Bitmap {
+        let info = BitmapInfo::new(width, height, depth, row_bytes);
+        let data = BitmapData::new(data.to_vec());
+
+        parse_bitmap_data(&info, &data, None, &PALETTE).unwrap()
+    }
+
+    #[test]
+    fn one_bit() {
+        let bitmap = decode(3, 2, 1, 2, &[0b1010_0000, 0, 0b0100_0000, 0]);
+
+        let black = [0, 0, 0, 255];
+        let white = [255, 255, 255, 255];
+
+        assert_eq!(bitmap.pixel(0, 0), Some(black));
+        assert_eq!(bitmap.pixel(1, 0), Some(white));
+        assert_eq!(bitmap.pixel(2, 0), Some(black));
+        assert_eq!(bitmap.pixel(1, 1), Some(black));
+        assert_eq!(bitmap.pixel(3, 0), None);
+        assert_eq!(bitmap.indices(), None);
+    }
+
+    #[test]
+    fn indexed() {
+        let bitmap = decode(4, 1, 2, 2, &[0b00_01_10_11, 0]);
+        assert_eq!(bitmap.indices(), Some(&vec![0, 1, 2, 3]));
+        assert_eq!(bitmap.pixel(1, 0), Some([255, 0, 0, 255]));
+        assert_eq!(bitmap.palette(), Some(&PALETTE.to_vec()));
+
+        let bitmap = decode(3, 1, 4, 2, &[0x12, 0x30]);
+        assert_eq!(bitmap.indices(), Some(&vec![1, 2, 3]));
+        assert_eq!(bitmap.pixel(2, 0), Some([0, 0, 0, 255]));
+
+        // Indices outside of the palette are black
+        let bitmap = decode(2, 1, 8, 2, &[2, 200]);
+        assert_eq!(bitmap.indices(), Some(&vec![2, 200]));
+        assert_eq!(bitmap.pixels(), &vec![0, 255, 0, 255, 0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn padded_rows() {
+        // The row bytes of the member are larger than the 2 bytes needed
+        let bitmap = decode(2, 2, 8, 4, &[1, 2, 9, 9, 3, 0, 9, 9]);
+        assert_eq!(bitmap.indices(), Some(&vec![1, 2, 3, 0]));
+        assert_eq!(bitmap.stride(), 8);
+        assert_eq!(bitmap.pixel(0, 1), Some([0, 0, 0, 255]));
+
+        // Row bytes that are too small to hold a row are ignored
+        let bitmap = decode(3, 2, 8, 1, &[1, 2, 3, 9, 3, 2, 1, 9]);
+        assert_eq!(bitmap.indices(), Some(&vec![1, 2, 3, 3, 2, 1]));
+    }
+
+    #[test]
+    fn compressed() {
+        let bitmap = decode(2, 2, 8, 2, &[0xfd, 0x01]);
+        assert_eq!(bitmap.indices(), Some(&vec![1; 4]));
+    }
+
+    #[test]
+    fn sixteen_bit() {
+        // The high bytes of both pixels, then their low bytes
+        let bitmap = decode(2, 1, 16, 4, &[0x7c, 0x03, 0x00, 0xe0]);
+
+        assert_eq!(bitmap.pixel(0, 0), Some([255, 0, 0, 255]));
+        assert_eq!(bitmap.pixel(1, 0), Some([0, 255, 0, 255]));
+        assert_eq!(bitmap.indices(), None);
+    }
+
+    #[test]
+    fn thirty_two_bit() {
+        // The alpha, red, green and blue planes of the row
+        let bitmap = decode(2, 1, 32, 8, &[0x80, 0xff, 1, 2, 3, 4, 5, 6]);
+
+        assert_eq!(bitmap.pixel(0, 0), Some([1, 3, 5, 0x80]));
+        assert_eq!(bitmap.pixel(1, 0), Some([2, 4, 6, 0xff]));
+        assert_eq!(bitmap.indices(), None);
+    }
+
+    #[test]
+    fn alpha_channel() {
+        let info = BitmapInfo::new(2, 2, 32, 8);
+        let data = BitmapData::new(vec![0xff; 16]);
+
+        let alpha = AlphaData::new(vec![10, 20, 30, 40]);
+        let bitmap = parse_bitmap_data(&info, &data, Some(&alpha), &PALETTE).unwrap();
+        assert_eq!(bitmap.pixel(0, 0), Some([0xff, 0xff, 0xff, 10]));
+        assert_eq!(bitmap.pixel(1, 1), Some([0xff, 0xff, 0xff, 40]));
+
+        // The alpha plane can be compressed too
+        let alpha = AlphaData::new(vec![0xfd, 0x80]);
+        let bitmap = parse_bitmap_data(&info, &data, Some(&alpha), &PALETTE).unwrap();
+        assert!(bitmap.pixels().chunks(4).all(|pixel| pixel[3] == 0x80));
+    }
+
+    #[test]
+    fn raw_data_is_used_as_it_is() {
+        // Would be a repeat run if it was compressed
+        let data = [0xfe, 0x01, 0x02, 0x03];
+
+        assert_eq!(decompress(&data, 4).unwrap(), data);
+    }
+
+    #[test]
+    fn literal_run() {
+        assert_eq!(unpack(&[0x02, 0x0a, 0x0b, 0x0c], 3).unwrap(), [0x0a, 0x0b, 0x0c]);
+    }
+
+    #[test]
+    fn repeat_run() {
+        assert_eq!(unpack(&[0xfd, 0x07], 4).unwrap(), [0x07; 4]);
+        assert_eq!(unpack(&[0xfe, 0x07, 0x00, 0x09], 4).unwrap(), [0x07, 0x07, 0x07, 0x09]);
+    }
+
+    #[test]
+    fn overrun() {
+        assert!(matches!(
+            unpack(&[0xfd, 0x07], 3),
+            Err(DirpError::BitmapOverrun { expected: 3, found: 4 })
+        ));
+    }
+
+    #[test]
+    fn underrun_is_filled_with_zeros() {
+        assert_eq!(unpack(&[0x00, 0x05], 3).unwrap(), [0x05, 0x00, 0x00]);
+        assert_eq!(unpack(&[0x03, 0x05], 3).unwrap(), [0x05, 0x00, 0x00]);
+    }
+}
@@ -0,0 +1,490 @@
+use crate::{DirectorFile, DirpError, Warning};
+
+use crate::endian;
+use crate::endian::{BigEndian, LittleEndian};
+
+use crate::chunk::rifx::Endianness;
+
+use crate::chunk::mcsl::Cast;
+
+use crate::chunk::cas;
+
+use crate::chunk::lscr::Script;
+
+use crate::chunk::lnam::NameTable;
+
+use crate::chunk::cast;
+use crate::chunk::cast::{BitmapInfo, CastKind, CastProperties, CastProperty, ScriptType};
+
+use crate::chunk::bitd;
+use crate::chunk::bitd::BitmapData;
+
+use crate::chunk::alfa;
+use crate::chunk::alfa::AlphaData;
+
+use crate::chunk::clut;
+use crate::chunk::clut::Palette;
+
+use crate::chunk::stxt;
+use crate::chunk::stxt::StyledText;
+
+use crate::bitmap;
+use crate::bitmap::Bitmap;
+
+use crate::palette::BuiltinPalette;
+
+// A cast library of a movie, with all of its members.
+pub struct CastLibrary {
+    number: u32,
+    name: String,
+    path: String,
+    id: u32,
+    members: Vec<CastMember>,
+    names: Option<NameTable>,
+    // The scripts of the cast, with their number in the script context.
+    scripts: Vec<(u32, Script)>,
+}
+
+impl CastLibrary {
+    // The number of the cast library in the movie, starting from 1.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // The path of an external cast, as stored in the movie.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // The ID of the cast library in the MCsL chunk.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn members(&self) -> &Vec<CastMember> {
+        &self.members
+    }
+
+    // Returns the member with the member number 'number'.
+    pub fn member(&self, number: u32) -> Option<&CastMember> {
+        self.members.iter().find(|member| member.number == number)
+    }
+
+    // The names used by the scripts of the cast.
+    pub fn names(&self) -> Option<&NameTable> {
+        self.names.as_ref()
+    }
+
+    pub fn scripts(&self) -> impl Iterator<Item = &Script> {
+        self.scripts.iter().map(|(_, script)| script)
+    }
+
+    // Returns the script with the number 'number' in the script context.
+    pub fn script(&self, number: u32) -> Option<&Script> {
+        self.scripts.iter()
+            .find(|(n, _)| *n == number)
+            .map(|(_, script)| script)
+    }
+}
+
+pub struct CastMember {
+    number: u32,
+    id: u32,
+    properties: CastProperties,
+    data: MemberData,
+    // The BITD and ALFA data of a bitmap, until it has been decoded.
+    bitmap_data: Option<BitmapData>,
+    alpha_data: Option<AlphaData>,
+}
+
+impl CastMember {
+    // The number of the member in its cast library.
+    pub fn number(&self) -> u32 {
+        self.number
+    }
+
+    // The resource ID of the CASt chunk of the member.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn kind(&self) -> CastKind {
+        self.properties.kind()
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.properties.properties()
+            .get(&CastProperty::Name)
+            .and_then(|name| name.downcast_ref::<String>())
+            .map(|name| name.as_str())
+    }
+
+    pub fn properties(&self) -> &CastProperties {
+        &self.properties
+    }
+
+    // The type specific properties of a bitmap member.
+    pub fn bitmap_info(&self) -> Option<&BitmapInfo> {
+        self.properties.properties()
+            .get(&CastProperty::BitmapInfo)
+            .and_then(|info| info.downcast_ref::<BitmapInfo>())
+    }
+
+    // The kind of a script member, if the movie stores it.
+    pub fn script_type(&self) -> Option<ScriptType> {
+        self.properties.properties()
+            .get(&CastProperty::ScriptType)
+            .and_then(|script_type| script_type.downcast_ref::<ScriptType>())
+            .copied()
+    }
+
+    // The Lingo source of a script member, which protected movies don't have.
+    pub fn script_text(&self) -> Option<&str> {
+        if self.kind() != CastKind::Script {
+            return None;
+        }
+
+        match &self.data {
+            MemberData::Text(text) => Some(text.text()),
+            _ => {
+                self.properties.properties()
+                    .get(&CastProperty::ScriptText)
+                    .and_then(|text| text.downcast_ref::<String>())
+                    .map(|text| text.as_str())
+            },
+        }
+    }
+
+    pub fn data(&self) -> &MemberData {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+impl CastMember {
+    // A bitmap member that has already been decoded.
+    pub(crate) fn bitmap(number: u32, info: BitmapInfo, bitmap: Bitmap) -> CastMember {
+        let properties = vec![
+            (CastProperty::BitmapInfo, Box::new(info) as Box<dyn std::any::Any>),
+        ];
+
+        CastMember {
+            number,
+            id: 0,
+            properties: CastProperties::new(CastKind::Bitmap, properties),
+            data: MemberData::Bitmap(bitmap),
+            bitmap_data: None,
+            alpha_data: None,
+        }
+    }
+}
+
+// The decoded data owned by a cast member.
+pub enum MemberData {
+    Bitmap(Bitmap),
+    Palette(Palette),
+    Text(StyledText),
+    None,
+}
+
+// Read the members of the cast library 'entry' from 'file'. The CAS* chunk
+// listing the members is owned by 'owner' in the key table.
+// The bitmaps are decoded by decode_bitmaps, once all cast libraries
+// have been read.
+pub(crate) fn read_cast_library(
+    file: &DirectorFile,
+    number: u32,
+    entry: &Cast,
+    owner: u32,
+    warnings: &mut Vec<Warning>
+) -> Result<CastLibrary, DirpError> {
+    // An external cast doesn't have to use the same endianness as the movie.
+    match file.header().endian() {
+        Endianness::Big => read_members::<BigEndian>(file, number, entry, owner, warnings),
+        Endianness::Little => read_members::<LittleEndian>(file, number, entry, owner, warnings),
+    }
+}
+
+fn read_members<E: endian::Endianness>(
+    file: &DirectorFile,
+    number: u32,
+    entry: &Cast,
+    owner: u32,
+    warnings: &mut Vec<Warning>
+) -> Result<CastLibrary, DirpError> {
+    let mut members = Vec::new();
+
+    // A cast without any members doesn't have a CAS* chunk.
+    if let Some(cas_id) = file.key().lookup(owner, b"CAS*") {
+        let cas = cas::read_cas::<_, E>(&mut file.chunk_reader(cas_id)?)?;
+
+        for (index, &id) in cas.members().iter().enumerate() {
+            if id == 0 {
+                continue;
+            }
+
+            let number = entry.min() as u32 + index as u32;
+
+            // A member that can't be read is skipped, so that
+            // the rest of the cast can still be read.
+            match read_member::<E>(file, entry.name(), number, id, warnings) {
+                Ok(member) => members.push(member),
+                Err(error) => {
+                    warnings.push(Warning::UnreadableMember {
+                        cast: entry.name().clone(),
+                        member: number,
+                        error,
+                    });
+                },
+            }
+        }
+    }
+
+    // The scripts are read through the script context of the cast
+    let (names, scripts) = match read_scripts(file, entry.name(), owner, warnings) {
+        Ok(scripts) => scripts,
+        Err(error) => {
+            warnings.push(Warning::UnreadableScripts {
+                cast: entry.name().clone(),
+                error,
+            });
+            (None, Vec::new())
+        },
+    };
+
+    Ok(CastLibrary {
+        number,
+        name: entry.name().clone(),
+        path: entry.path().clone(),
+        id: entry.id(),
+        members,
+        names,
+        scripts,
+    })
+}
+
+type Scripts = (Option<NameTable>, Vec<(u32, Script)>);
+
+// Read the name table and scripts of the script context owned by 'owner'.
+// Scripts that can't be read are skipped, with a warning.
+fn read_scripts(
+    file: &DirectorFile,
+    cast: &str,
+    owner: u32,
+    warnings: &mut Vec<Warning>
+) -> Result<Scripts, DirpError> {
+    let context = match file.script_context(owner)? {
+        Some(context) => context,
+        None => return Ok((None, Vec::new())),
+    };
+
+    let names = file.name_table(&context)?;
+
+    let mut scripts = Vec::new();
+
+    for (number, _) in context.scripts() {
+        match file.context_script(&context, number) {
+            Ok(Some(script)) => scripts.push((number, script)),
+            Ok(None) => {},
+            Err(error) => {
+                warnings.push(Warning::UnreadableScript {
+                    cast: cast.to_string(),
+                    script: number,
+                    error,
+                });
+            },
+        }
+    }
+
+    Ok((Some(names), scripts))
+}
+
+fn read_member<E: endian::Endianness>(
+    file: &DirectorFile,
+    cast: &str,
+    number: u32,
+    id: u32,
+    warnings: &mut Vec<Warning>
+) -> Result<CastMember, DirpError> {
+    let properties = cast::read_cast::<_, E>(&mut file.chunk_reader(id)?)?;
+
+    for &index in properties.unparsed() {
+        warnings.push(Warning::UnparsedProperty {
+            cast: cast.to_string(),
+            member: number,
+            index,
+        });
+    }
+
+    let mut bitmap_data = None;
+    let mut alpha_data = None;
+
+    let data = match properties.kind() {
+        CastKind::Bitmap => {
+            // Bitmaps own the BITD chunk
+            match file.key().lookup(id, b"BITD") {
+                Some(bitd_id) => {
+                    let depth = crate::property::<BitmapInfo>(&properties, CastProperty::BitmapInfo)?.depth();
+
+                    if bitmap::is_supported(depth) {
+                        let data = bitd::read_bitd::<_, E>(&mut file.chunk_reader(bitd_id)?)?;
+
+                        bitmap_data = Some(data);
+
+                        // 32-bit bitmaps may have their alpha
+                        // channel in a separate ALFA chunk
+                        if depth == 32 {
+                            if let Some(alfa_id) = file.key().lookup(id, b"ALFA") {
+                                let data = alfa::read_alfa::<_, E>(&mut file.chunk_reader(alfa_id)?)?;
+
+                                alpha_data = Some(data);
+                            }
+                        }
+                    } else {
+                        warnings.push(Warning::UnsupportedBitDepth {
+                            cast: cast.to_string(),
+                            member: number,
+                            depth,
+                        });
+                    }
+                },
+                // TODO For some reason, some of the bitmap casts
+                // doesn't own a BITD chunk...?
+                None => {
+                    warnings.push(Warning::MissingBitmapData {
+                        cast: cast.to_string(),
+                        member: number,
+                    });
+                },
+            }
+
+            MemberData::None
+        },
+        CastKind::Palette => {
+            // Palettes own the CLUT chunk
+            match file.key().lookup(id, b"CLUT") {
+                Some(clut_id) => MemberData::Palette(clut::read_clut::<_, E>(&mut file.chunk_reader(clut_id)?)?),
+                None => {
+                    warnings.push(Warning::MissingPaletteData {
+                        cast: cast.to_string(),
+                        member: number,
+                    });
+                    MemberData::None
+                },
+            }
+        },
+        CastKind::Script => {
+            // Scripts may own an STXT chunk with their source,
+            // unless the movie has been protected
+            match file.key().lookup(id, b"STXT") {
+                Some(stxt_id) => MemberData::Text(stxt::read_stxt::<_, E>(&mut file.chunk_reader(stxt_id)?)?),
+                None => MemberData::None,
+            }
+        },
+        kind => {
+            warnings.push(Warning::UnsupportedCastKind {
+                cast: cast.to_string(),
+                member: number,
+                kind,
+            });
+            MemberData::None
+        }
+    };
+
+    Ok(CastMember {
+        number,
+        id,
+        properties,
+        data,
+        bitmap_data,
+        alpha_data,
+    })
+}
+
+// Decode the bitmaps of all cast libraries, now that the palettes
+// they refer to have been read.
+pub(crate) fn decode_bitmaps(
+    casts: &mut [CastLibrary],
+    warnings: &mut Vec<Warning>
+) -> Result<(), DirpError> {
+    let mut bitmaps = Vec::new();
+
+    for (l, library) in casts.iter().enumerate() {
+        for (m, member) in library.members.iter().enumerate() {
+            let data = match &member.bitmap_data {
+                Some(data) => data,
+                None => continue,
+            };
+
+            let info = crate::property::<BitmapInfo>(&member.properties, CastProperty::BitmapInfo)?;
+
+            let palette = match palette(casts, library, info) {
+                Some(palette) => palette,
+                None => {
+                    warnings.push(Warning::MissingPalette {
+                        cast: library.name.clone(),
+                        member: member.number,
+                    });
+                    BuiltinPalette::SystemMac.colors()
+                },
+            };
+
+            let bitmap = bitmap::parse_bitmap_data(
+                info, data, member.alpha_data.as_ref(), &palette
+            );
+
+            match bitmap {
+                Ok(bitmap) => bitmaps.push((l, m, bitmap)),
+                Err(error) => {
+                    warnings.push(Warning::UndecodableBitmap {
+                        cast: library.name.clone(),
+                        member: member.number,
+                        error,
+                    });
+                },
+            }
+        }
+    }
+
+    for (l, m, bitmap) in bitmaps {
+        let member = &mut casts[l].members[m];
+
+        member.data = MemberData::Bitmap(bitmap);
+        member.bitmap_data = None;
+        member.alpha_data = None;
+    }
+
+    Ok(())
+}
+
+// Returns the colors of the palette used by a bitmap in 'library',
+// or None if the palette cast member can't be found.
+fn palette(
+    casts: &[CastLibrary],
+    library: &CastLibrary,
+    info: &BitmapInfo
+) -> Option<Vec<[u8; 3]>> {
+    let cast = info.palette_cast();
+    let id = info.palette();
+
+    if id <= 0 {
+        return BuiltinPalette::from_id(id).map(|palette| palette.colors());
+    }
+
+    let library = if cast > 0 {
+        casts.iter().find(|library| library.number == cast as u32)
+    } else {
+        Some(library)
+    };
+
+    library
+        .and_then(|library| library.member(id as u32))
+        .and_then(|member| match &member.data {
+            MemberData::Palette(palette) => Some(palette.rgb()),
+            _ => None,
+        })
+}
@@ -6,6 +6,25 @@ pub mod mcsl;
 pub mod cas;
 pub mod cast;
 pub mod bitd;
+pub mod alfa;
+pub mod clut;
+pub mod stxt;
+pub mod lscr;
+pub mod lctx;
+pub mod lnam;
+pub mod drcf;
+pub mod vwsc;
+pub mod fver;
+pub mod fcdr;
+pub mod abmp;
+pub mod fgei;
+
+use std::io::{Read, Seek};
+
+use flate2::read::ZlibDecoder;
+
+use crate::endian::{self, Endian};
+use crate::error::DirpError;
 
 use rifx::Header;
 use imap::InitialMap;
@@ -15,6 +34,18 @@ use mcsl::MovieCastList;
 use cas::CastTable;
 use cast::CastProperties;
 use bitd::BitmapData;
+use alfa::AlphaData;
+use clut::Palette;
+use stxt::StyledText;
+use lscr::Script;
+use lctx::ScriptContext;
+use lnam::NameTable;
+use drcf::Config;
+use vwsc::Score;
+use fver::FileVersion;
+use fcdr::CompressionTable;
+use abmp::AfterburnerMap;
+use fgei::InitialLoadSegment;
 
 pub enum Chunk {
     Header(Header),
@@ -25,4 +56,47 @@ pub enum Chunk {
     CastTable(CastTable),
     CastProperties(CastProperties),
     BitmapData(BitmapData),
+    AlphaData(AlphaData),
+    Palette(Palette),
+    StyledText(StyledText),
+    Script(Script),
+    ScriptContext(ScriptContext),
+    NameTable(NameTable),
+    Config(Config),
+    Score(Score),
+    FileVersion(FileVersion),
+    CompressionTable(CompressionTable),
+    AfterburnerMap(AfterburnerMap),
+    InitialLoadSegment(InitialLoadSegment),
+}
+
+// Read the FourCC at the start of a chunk and make sure that it is 'expected'.
+fn read_fourcc<R: Read + Seek + Endian, E: endian::Endianness>(
+    file: &mut R,
+    expected: &[u8; 4]
+) -> Result<(), DirpError> {
+    let offset = file.stream_position()?;
+
+    let mut found = [0; 4];
+    file.read_bytes::<E>(&mut found)?;
+    if &found != expected {
+        return Err(DirpError::InvalidChunk {
+            expected: *expected,
+            found,
+            offset,
+        });
+    }
+
+    Ok(())
+}
+
+// Decompress zlib compressed Afterburner data.
+pub(crate) fn decompress(data: &[u8]) -> Result<Vec<u8>, DirpError> {
+    let mut decompressed = Vec::new();
+
+    ZlibDecoder::new(data)
+        .read_to_end(&mut decompressed)
+        .map_err(DirpError::Decompression)?;
+
+    Ok(decompressed)
 }
@@ -0,0 +1,109 @@
+use std::io::{Cursor, Read, Seek};
+
+use crate::endian::{Endian, Endianness};
+use crate::error::DirpError;
+
+// The ABMP chunk is the memory map of an Afterburner movie.
+pub struct AfterburnerMap {
+    entries: Vec<AfterburnerMapEntry>,
+}
+
+impl AfterburnerMap {
+    pub fn entries(&self) -> &Vec<AfterburnerMapEntry> {
+        &self.entries
+    }
+
+    // Returns the entry for the resource ID 'id'.
+    pub fn entry(&self, id: u32) -> Result<&AfterburnerMapEntry, DirpError> {
+        self.entries.iter()
+            .find(|entry| entry.id == id)
+            .ok_or(DirpError::MissingChunk(id))
+    }
+}
+
+pub struct AfterburnerMapEntry {
+    id: u32,
+    chunk: String,
+    offset: i32,
+    compressed_size: u32,
+    size: u32,
+    compression: u32,
+}
+
+impl AfterburnerMapEntry {
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    pub fn chunk(&self) -> &str {
+        &self.chunk
+    }
+
+    // The offset of the chunk data, relative to the end of the FGEI header.
+    pub fn offset(&self) -> i32 {
+        self.offset
+    }
+
+    pub fn compressed_size(&self) -> u32 {
+        self.compressed_size
+    }
+
+    // The size of the chunk data after decompression.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    // The index of the compression type in the Fcdr chunk.
+    pub fn compression(&self) -> u32 {
+        self.compression
+    }
+}
+
+pub fn read_abmp<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<AfterburnerMap, DirpError> {
+    super::read_fourcc::<R, E>(file, b"ABMP")?;
+
+    let size = file.read_varint()?;
+    let end = file.stream_position()? + size as u64;
+
+    let _compression = file.read_varint()?;
+    let _uncompressed_size = file.read_varint()?;
+
+    let start = file.stream_position()?;
+
+    let mut data = vec![0; end.saturating_sub(start) as usize];
+    file.read_exact(&mut data)?;
+
+    let mut data = Cursor::new(super::decompress(&data)?);
+
+    data.read_varint()?;
+    data.read_varint()?;
+
+    let count = data.read_varint()?;
+
+    let mut entries = Vec::new();
+
+    for _ in 0..count {
+        let id = data.read_varint()?;
+        let offset = data.read_varint()? as i32;
+        let compressed_size = data.read_varint()?;
+        let size = data.read_varint()?;
+        let compression = data.read_varint()?;
+
+        let mut chunk = [0; 4];
+        data.read_bytes::<E>(&mut chunk)?;
+        let chunk = String::from_utf8_lossy(&chunk).into_owned();
+
+        entries.push(AfterburnerMapEntry {
+            id,
+            chunk,
+            offset,
+            compressed_size,
+            size,
+            compression,
+        });
+    }
+
+    Ok(AfterburnerMap {
+        entries,
+    })
+}
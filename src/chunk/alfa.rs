@@ -0,0 +1,38 @@
+use std::io::{Read, Seek};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The ALFA chunk holds the alpha plane of a 32-bit bitmap,
+// compressed in the same way as the BITD chunk.
+pub struct AlphaData {
+    data: Vec<u8>,
+}
+
+impl AlphaData {
+    pub fn data(&self) -> &Vec<u8> {
+        &self.data
+    }
+}
+
+#[cfg(test)]
+impl AlphaData {
+    pub(crate) fn new(data: Vec<u8>) -> AlphaData {
+        AlphaData {
+            data,
+        }
+    }
+}
+
+pub fn read_alfa<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<AlphaData, DirpError> {
+    super::read_fourcc::<R, E>(file, b"ALFA")?;
+
+    let size = file.read_u32::<E>()?;
+
+    let mut data = vec![0; size as usize];
+    file.read_bytes::<BigEndian>(&mut data)?;
+
+    Ok(AlphaData {
+        data,
+    })
+}
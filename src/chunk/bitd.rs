@@ -1,6 +1,7 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
 use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
 
 pub struct BitmapData {
     data: Vec<u8>,
@@ -12,19 +13,24 @@ impl BitmapData {
     }
 }
 
-pub fn read_bitd<R: Read + Endian, E: Endianness>(file: &mut R) -> BitmapData {
-    let mut mmap = [0; 4];
-    file.read_bytes::<E>(&mut mmap);
-    if mmap != [b'B', b'I', b'T', b'D'] {
-        panic!("Chunk header was incorrect");
+#[cfg(test)]
+impl BitmapData {
+    pub(crate) fn new(data: Vec<u8>) -> BitmapData {
+        BitmapData {
+            data,
+        }
     }
+}
 
-    let size = file.read_u32::<E>();
+pub fn read_bitd<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<BitmapData, DirpError> {
+    super::read_fourcc::<R, E>(file, b"BITD")?;
+
+    let size = file.read_u32::<E>()?;
 
     let mut data = vec![0; size as usize];
-    file.read_bytes::<BigEndian>(&mut data);
+    file.read_bytes::<BigEndian>(&mut data)?;
 
-    BitmapData {
-        data: data,
-    }
+    Ok(BitmapData {
+        data,
+    })
 }
@@ -1,6 +1,7 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
 use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
 
 pub struct CastTable {
     members: Vec<Member>,
@@ -14,28 +15,24 @@ impl CastTable {
 
 type Member = u32;
 
-pub fn read_cas<R: Read + Endian, E: Endianness>(file: &mut R) -> CastTable {
-    let mut key = [0; 4];
-    file.read_bytes::<E>(&mut key);
-    if key != [b'C', b'A', b'S', b'*'] {
-        panic!("Chunk header was incorrect");
-    }
+pub fn read_cas<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<CastTable, DirpError> {
+    super::read_fourcc::<R, E>(file, b"CAS*")?;
 
-    let size = file.read_u32::<E>();
+    let size = file.read_u32::<E>()?;
 
-    eprintln!("Size of CAS*: {}", size);
+    log::trace!("Size of CAS*: {}", size);
 
     let member_count = size / std::mem::size_of::<u32>() as u32;
 
     let mut members = Vec::new();
 
     for _ in 0..member_count {
-        let member = file.read_u32::<BigEndian>();
-        eprintln!("Cast member: {:08x}", member);
+        let member = file.read_u32::<BigEndian>()?;
+        log::trace!("Cast member: {:08x}", member);
         members.push(member);
     }
 
-    CastTable {
-        members: members,
-    }
+    Ok(CastTable {
+        members,
+    })
 }
@@ -1,16 +1,18 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
 use std::collections::HashMap;
 
 use std::any::Any;
 
 use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
 
 pub struct CastProperties {
     kind: CastKind,
     // TODO It would be nice if we could change this to a HashSet of enums,
     // where only the enum type and not the value is part of the hash.
     properties: HashMap<CastProperty, Box<dyn Any>>,
+    unparsed: Vec<usize>,
 }
 
 impl CastProperties {
@@ -21,18 +23,151 @@ impl CastProperties {
     pub fn properties(&self) -> &HashMap<CastProperty, Box<dyn Any>> {
         &self.properties
     }
+
+    // The indices of the general properties that couldn't be parsed.
+    pub fn unparsed(&self) -> &Vec<usize> {
+        &self.unparsed
+    }
+}
+
+#[cfg(test)]
+impl CastProperties {
+    pub(crate) fn new(kind: CastKind, properties: Vec<Property>) -> CastProperties {
+        CastProperties {
+            kind,
+            properties: properties.into_iter().collect(),
+            unparsed: Vec::new(),
+        }
+    }
 }
 
-#[derive(PartialEq, Eq, Hash)]
+type Property = (CastProperty, Box<dyn Any>);
+
+#[derive(PartialEq, Eq, Hash, Debug)]
 pub enum CastProperty {
-    Name = 1,          // String
-    XtraName = 10,     // String
-    BitmapWidth = 22,  // usize
-    BitmapHeight = 23, // usize
-    BitmapDepth = 24,  // usize
+    ScriptText = 0,         // String
+    Name = 1,               // String
+    XtraName = 10,          // String
+    BitmapInfo = 22,        // BitmapInfo
+    ScriptType = 23,        // ScriptType
+}
+
+// A rectangle, as stored by Director.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub top: i16,
+    pub left: i16,
+    pub bottom: i16,
+    pub right: i16,
+}
+
+impl Rect {
+    pub fn width(&self) -> usize {
+        self.right.saturating_sub(self.left).max(0) as usize
+    }
+
+    pub fn height(&self) -> usize {
+        self.bottom.saturating_sub(self.top).max(0) as usize
+    }
+}
+
+// The type specific properties of a bitmap cast member.
+#[derive(Debug)]
+pub struct BitmapInfo {
+    rect: Rect,
+    bounding_rect: Rect,
+    reg_x: i16,
+    reg_y: i16,
+    depth: usize,
+    palette_cast: i16,
+    palette: i16,
+    row_bytes: usize,
+    flags: u16,
+    update_flags: u8,
+}
+
+impl BitmapInfo {
+    // The rect of the bitmap, which gives its width and height.
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
+    pub fn bounding_rect(&self) -> Rect {
+        self.bounding_rect
+    }
+
+    pub fn width(&self) -> usize {
+        self.rect.width()
+    }
+
+    pub fn height(&self) -> usize {
+        self.rect.height()
+    }
+
+    // The registration point as (x, y), in the same coordinates as the rect.
+    pub fn registration_point(&self) -> (i16, i16) {
+        (self.reg_x, self.reg_y)
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    // The cast library number of the palette, or -1 for the
+    // same cast library as the bitmap.
+    pub fn palette_cast(&self) -> i16 {
+        self.palette_cast
+    }
+
+    // The member number of the palette, or zero or negative
+    // for a built-in palette.
+    pub fn palette(&self) -> i16 {
+        self.palette
+    }
+
+    // The number of bytes in a row of the decompressed BITD data.
+    pub fn row_bytes(&self) -> usize {
+        self.row_bytes
+    }
+
+    // The upper two bits of the row bytes value, which aren't interpreted.
+    // Whether a bit depth and palette follow the registration point is
+    // decided by the size of the properties instead.
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    // NOTE These flags hold the "update movie if edited elsewhere" and
+    // "trim white space" options of the member, but which bit is which
+    // hasn't been figured out yet.
+    pub fn update_flags(&self) -> u8 {
+        self.update_flags
+    }
 }
 
-#[derive(Copy, Clone)]
+// The kind of a script cast member, from its type specific properties.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ScriptType {
+    // A behavior, which is attached to sprites or frames in the score.
+    Score,
+    Movie,
+    Parent,
+    Unknown(u16),
+}
+
+impl ScriptType {
+    fn from_id(id: u16) -> ScriptType {
+        match id {
+            1 => ScriptType::Score,
+            3 => ScriptType::Movie,
+            7 => ScriptType::Parent,
+            id => ScriptType::Unknown(id),
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum CastKind {
     Bitmap = 1,
     FilmLoop,
@@ -51,19 +186,15 @@ pub enum CastKind {
     Xtra,
 }
 
-pub fn read_cast<R: Read + Endian, E: Endianness>(file: &mut R) -> CastProperties {
-    let mut key = [0; 4];
-    file.read_bytes::<E>(&mut key);
-    if key != [b'C', b'A', b'S', b't'] {
-        panic!("Chunk header was incorrect");
-    }
+pub fn read_cast<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<CastProperties, DirpError> {
+    super::read_fourcc::<R, E>(file, b"CASt")?;
 
-    let _size = file.read_u32::<E>();
+    let _size = file.read_u32::<E>()?;
     let mut read = 0;
 
-    let kind = file.read_u32::<BigEndian>();
+    let kind = file.read_u32::<BigEndian>()?;
     read += 4;
-    eprintln!("cast type: {}", kind);
+    log::debug!("cast type: {}", kind);
     let kind = match kind {
         1 =>  CastKind::Bitmap,
         2 =>  CastKind::FilmLoop,
@@ -80,124 +211,194 @@ pub fn read_cast<R: Read + Endian, E: Endianness>(file: &mut R) -> CastPropertie
         13 => CastKind::OLE,
         14 => CastKind::Transition,
         15 => CastKind::Xtra,
-        _ => panic!("Unknown cast type: {}", kind),
+        _ => return Err(DirpError::UnknownCastKind(kind)),
     };
 
     // This seem to be the size of the general properties.
-    let _a = file.read_u32::<BigEndian>();
+    let general_size = file.read_u32::<BigEndian>()?;
     read += 4;
     // This seem to be the size of the type specific properties.
-    let _b = file.read_u32::<BigEndian>();
+    let specific_size = file.read_u32::<BigEndian>()?;
     read += 4;
 
-    eprintln!("{} {}", _a, _b);
+    log::trace!("{} {}", general_size, specific_size);
 
-    // NOTE the _a size is from here
+    // NOTE the general_size is from here
 
-    let _c = file.read_u32::<BigEndian>();
+    let _c = file.read_u32::<BigEndian>()?;
     read += 4;
-    let _d = file.read_u32::<BigEndian>();
+    let _d = file.read_u32::<BigEndian>()?;
     read += 4;
-    let _e = file.read_u32::<BigEndian>();
+    let _e = file.read_u32::<BigEndian>()?;
     read += 4;
-    let _f = file.read_u32::<BigEndian>();
+    let _f = file.read_u32::<BigEndian>()?;
     read += 4;
-    let _g = file.read_u32::<BigEndian>();
+    let _g = file.read_u32::<BigEndian>()?;
     read += 4;
 
-    let offset_count = file.read_u16::<BigEndian>();
+    let offset_count = file.read_u16::<BigEndian>()?;
     read += 2;
 
     let mut offsets: Vec<usize> = Vec::new();
 
-    for _ in 0..(offset_count + 1) {
-        let offset = file.read_u32::<BigEndian>() as usize;
+    for _ in 0..=offset_count {
+        let offset = file.read_u32::<BigEndian>()? as usize;
         read += 4;
         offsets.push(offset);
     }
 
     let mut properties = HashMap::new();
+    let mut unparsed = Vec::new();
 
     for i in 0..offset_count as usize {
-        let len = offsets[i + 1] - offsets[i];
+        let len = offsets[i + 1].saturating_sub(offsets[i]);
         if len == 0 {
             continue;
         }
 
-        let prop = read_property(file, i, len);
+        let prop = read_property(file, i, len)?;
         read += len;
         if let Some((name, value)) = prop {
             properties.insert(name, value);
+        } else {
+            unparsed.push(i);
         }
     }
 
-    // NOTE The _b size is from here
+    // Skip whatever is left of the general properties,
+    // so that we are at the start of the type specific ones.
+    let general_end = 12 + general_size as usize;
+    if read < general_end {
+        let mut scrap = vec![0; general_end - read];
+        file.read_bytes::<BigEndian>(&mut scrap)?;
+    }
+
+    // NOTE The specific_size is from here
 
     // TODO Parse the properties for the specific type
 
     match kind {
         CastKind::Bitmap => {
-            file.read_u16::<BigEndian>();
+            let info = read_bitmap_info(file, specific_size as usize)?;
 
-            let top = file.read_u16::<BigEndian>();
-            let left = file.read_u16::<BigEndian>();
-            let bottom = file.read_u16::<BigEndian>();
-            let right = file.read_u16::<BigEndian>();
+            log::trace!("{:?}", info);
 
-            eprintln!("{} {} {} {}", top, left, bottom, right);
-
-            let height = bottom - top;
-            let width = right - left;
-
-            file.read_u32::<BigEndian>();
-            file.read_u32::<BigEndian>();
+            properties.insert(CastProperty::BitmapInfo, Box::new(info));
+        },
+        // Scripts from before Director 5 may not have a type
+        CastKind::Script if specific_size >= 2 => {
+            let script_type = ScriptType::from_id(file.read_u16::<BigEndian>()?);
 
-            let point_x = file.read_u16::<BigEndian>();
-            let point_y = file.read_u16::<BigEndian>();
+            log::trace!("{:?}", script_type);
 
-            eprintln!("{} {}", point_x, point_y);
+            properties.insert(CastProperty::ScriptType, Box::new(script_type));
+        },
+        _ => {
+            log::debug!("Unsupported cast type, skipping type specific properties...");
+        },
+    }
 
-            let _a = file.read_u8();
-            let bit_depth = file.read_u8();
+    Ok(CastProperties {
+        kind,
+        properties,
+        unparsed,
+    })
+}
 
-            eprintln!("{} {}", _a, bit_depth);
+fn read_rect<R: Read + Endian>(file: &mut R) -> Result<Rect, DirpError> {
+    Ok(Rect {
+        top: file.read_i16::<BigEndian>()?,
+        left: file.read_i16::<BigEndian>()?,
+        bottom: file.read_i16::<BigEndian>()?,
+        right: file.read_i16::<BigEndian>()?,
+    })
+}
 
-            // _c is always -1 and _d is always -101
-            let _c = file.read_u16::<BigEndian>() as i16;
-            let _d = file.read_u16::<BigEndian>() as i16;
+#[cfg(test)]
+impl BitmapInfo {
+    pub(crate) fn new(width: i16, height: i16, depth: usize, row_bytes: usize) -> BitmapInfo {
+        let rect = Rect {
+            top: 0,
+            left: 0,
+            bottom: height,
+            right: width,
+        };
+
+        BitmapInfo {
+            rect,
+            bounding_rect: rect,
+            reg_x: 0,
+            reg_y: 0,
+            depth,
+            palette_cast: -1,
+            palette: 0,
+            row_bytes,
+            flags: 0,
+            update_flags: 0,
+        }
+    }
+}
 
+fn read_bitmap_info<R: Read + Endian>(file: &mut R, size: usize) -> Result<BitmapInfo, DirpError> {
+    let row_bytes = file.read_u16::<BigEndian>()?;
+
+    let rect = read_rect(file)?;
+    let bounding_rect = read_rect(file)?;
+
+    // NOTE The registration point is stored as y before x
+    let reg_y = file.read_i16::<BigEndian>()?;
+    let reg_x = file.read_i16::<BigEndian>()?;
+
+    let mut info = BitmapInfo {
+        rect,
+        bounding_rect,
+        reg_x,
+        reg_y,
+        depth: 1,
+        palette_cast: -1,
+        palette: 0,
+        row_bytes: (row_bytes & 0x3fff) as usize,
+        flags: row_bytes & 0xc000,
+        update_flags: 0,
+    };
 
-            properties.insert(
-                CastProperty::BitmapWidth, Box::new(width as usize)
-            );
-            properties.insert(
-                CastProperty::BitmapHeight, Box::new(height as usize)
-            );
-            properties.insert(
-                CastProperty::BitmapDepth, Box::new(bit_depth as usize)
-            );
-        },
-        _ => {
-            eprintln!("Unsupported cast type, skipping type specific properties...");
-        },
+    // 1-bit bitmaps may end here, in which case
+    // they use the System Mac palette.
+    if size < 28 {
+        return Ok(info);
     }
 
-    eprintln!();
+    info.update_flags = file.read_u8()?;
+    info.depth = file.read_u8()? as usize;
 
-    CastProperties {
-        kind: kind,
-        properties: properties,
-    }
+    // The cast library and member number of the palette. The cast
+    // library is -1 for the same cast as the bitmap, and the member
+    // number is zero or negative for a built-in palette.
+    info.palette_cast = file.read_i16::<BigEndian>()?;
+    info.palette = file.read_i16::<BigEndian>()?;
+
+    Ok(info)
 }
 
 fn read_property<R: Read + Endian>(
     file: &mut R,
     index: usize,
     len: usize
-) -> Option<(CastProperty, Box<dyn Any>)> {
+) -> Result<Option<Property>, DirpError> {
     match index {
+        0 => {
+            // The Lingo source of a script, which is removed when the
+            // movie is protected. It is often Mac Roman rather than UTF-8,
+            // so invalid bytes are replaced.
+            let mut bytes = vec![0; len];
+            file.read_bytes::<BigEndian>(&mut bytes)?;
+
+            let text = String::from_utf8_lossy(&bytes).replace("\r\n", "\n").replace('\r', "\n");
+
+            Ok(Some((CastProperty::ScriptText, Box::new(text))))
+        },
         1 => {
-            let str_len = file.read_u8() as usize;
+            let str_len = file.read_u8()? as usize;
 
             // Make sure that we don't read more bytes than
             // the given length of the property.
@@ -207,32 +408,51 @@ fn read_property<R: Read + Endian>(
                 str_len
             };
 
-            let mut name = vec![0; str_len];
-            file.read_bytes::<BigEndian>(&mut name);
-            let name = String::from_utf8(name).unwrap();
-            //let _null = file.read_u8();
+            let name = file.read_string(str_len)?;
+
+            // Skip the rest of the property, which is usually
+            // a NULL byte after the name.
+            let mut rest = vec![0; len - 1 - str_len];
+            file.read_bytes::<BigEndian>(&mut rest)?;
 
-            eprintln!("name: {}", name);
+            log::debug!("name: {}", name);
 
-            Some((CastProperty::Name, Box::new(name)))
+            Ok(Some((CastProperty::Name, Box::new(name))))
         },
         10 => {
             // NOTE This will be terminated by a NULL byte,
-            // which we don't strip.
-            let mut name = vec![0; len];
-            file.read_bytes::<BigEndian>(&mut name);
-            let name = String::from_utf8(name).unwrap();
-            //let _null = file.read_u8();
+            // which we strip.
+            let name = file.read_string(len)?;
+            let name = name.trim_end_matches('\0').to_string();
 
-            eprintln!("name: {}", name);
+            log::debug!("name: {}", name);
 
-            Some((CastProperty::XtraName, Box::new(name)))
+            Ok(Some((CastProperty::XtraName, Box::new(name))))
         },
         i => {
             let mut scrap = vec![0; len];
-            file.read_bytes::<BigEndian>(&mut scrap);
-            eprintln!("Can't parse cast property with index {}", i);
-            None
+            file.read_bytes::<BigEndian>(&mut scrap)?;
+            log::debug!("Can't parse cast property with index {}", i);
+            Ok(None)
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn name_property() {
+        // The name is followed by a NULL byte and then the next property
+        let mut file = Cursor::new(b"\x03abc\0\xaa".to_vec());
+
+        let (property, value) = read_property(&mut file, 1, 5).unwrap().unwrap();
+
+        assert_eq!(property, CastProperty::Name);
+        assert_eq!(value.downcast_ref::<String>().unwrap(), "abc");
+        assert_eq!(file.read_u8().unwrap(), 0xaa);
+    }
+}
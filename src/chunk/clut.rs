@@ -0,0 +1,46 @@
+use std::io::{Read, Seek};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The CLUT chunk holds the colors of a palette cast member.
+pub struct Palette {
+    colors: Vec<[u16; 3]>,
+}
+
+impl Palette {
+    // The colors as 16-bit red, green and blue values.
+    pub fn colors(&self) -> &Vec<[u16; 3]> {
+        &self.colors
+    }
+
+    // The colors as 8-bit red, green and blue values.
+    pub fn rgb(&self) -> Vec<[u8; 3]> {
+        self.colors.iter()
+            .map(|&[r, g, b]| [(r >> 8) as u8, (g >> 8) as u8, (b >> 8) as u8])
+            .collect()
+    }
+}
+
+pub fn read_clut<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<Palette, DirpError> {
+    super::read_fourcc::<R, E>(file, b"CLUT")?;
+
+    let size = file.read_u32::<E>()?;
+
+    // Every color is three 16-bit values
+    let count = size / 6;
+
+    let mut colors = Vec::new();
+
+    for _ in 0..count {
+        let r = file.read_u16::<BigEndian>()?;
+        let g = file.read_u16::<BigEndian>()?;
+        let b = file.read_u16::<BigEndian>()?;
+
+        colors.push([r, g, b]);
+    }
+
+    Ok(Palette {
+        colors,
+    })
+}
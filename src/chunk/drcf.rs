@@ -0,0 +1,97 @@
+use std::io::{Read, Seek};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The DRCF (or VWCF) chunk holds the configuration of a movie.
+// Only the fields up to the Director version are parsed.
+pub struct Config {
+    file_version: u16,
+    stage: [i16; 4],
+    min_member: i16,
+    max_member: i16,
+    director_version: i16,
+}
+
+impl Config {
+    pub fn file_version(&self) -> u16 {
+        self.file_version
+    }
+
+    // The top, left, bottom and right of the stage.
+    pub fn stage(&self) -> [i16; 4] {
+        self.stage
+    }
+
+    pub fn min_member(&self) -> i16 {
+        self.min_member
+    }
+
+    pub fn max_member(&self) -> i16 {
+        self.max_member
+    }
+
+    // The internal version number of Director that saved the movie.
+    pub fn director_version(&self) -> i16 {
+        self.director_version
+    }
+
+    // The version of Director that saved the movie, as in 850 for 8.5.
+    pub fn version(&self) -> u32 {
+        match self.director_version {
+            v if v >= 1951 => 1201,
+            v if v >= 1923 => 1200,
+            v if v >= 1922 => 1150,
+            v if v >= 1921 => 1100,
+            v if v >= 1851 => 1000,
+            v if v >= 1700 => 850,
+            v if v >= 1410 => 800,
+            v if v >= 1224 => 700,
+            v if v >= 1218 => 600,
+            v if v >= 1201 => 500,
+            v if v >= 1117 => 404,
+            v if v >= 1115 => 400,
+            v if v >= 1029 => 310,
+            v if v >= 1028 => 300,
+            _ => 200,
+        }
+    }
+}
+
+// The body of the configuration is always big endian, no matter the
+// endianness of the movie. 'chunk' is either DRCF or VWCF.
+pub fn read_drcf<R: Read + Seek + Endian, E: Endianness>(
+    file: &mut R,
+    chunk: &[u8; 4]
+) -> Result<Config, DirpError> {
+    super::read_fourcc::<R, E>(file, chunk)?;
+
+    let _size = file.read_u32::<E>()?;
+
+    let _len = file.read_u16::<BigEndian>()?;
+    let file_version = file.read_u16::<BigEndian>()?;
+
+    let mut stage = [0; 4];
+    for value in stage.iter_mut() {
+        *value = file.read_i16::<BigEndian>()?;
+    }
+
+    let min_member = file.read_i16::<BigEndian>()?;
+    let max_member = file.read_i16::<BigEndian>()?;
+
+    // Fonts, colors and other settings we don't parse yet
+    let mut scrap = [0; 20];
+    file.read_bytes::<BigEndian>(&mut scrap)?;
+
+    let director_version = file.read_i16::<BigEndian>()?;
+
+    log::debug!("Director version: {}", director_version);
+
+    Ok(Config {
+        file_version,
+        stage,
+        min_member,
+        max_member,
+        director_version,
+    })
+}
@@ -0,0 +1,92 @@
+use std::io::{Cursor, Read, Seek};
+
+use crate::endian::{Endian, Endianness};
+use crate::error::DirpError;
+
+// The Fcdr chunk lists the compression types used by an Afterburner movie.
+pub struct CompressionTable {
+    entries: Vec<Compression>,
+}
+
+impl CompressionTable {
+    pub fn entries(&self) -> &Vec<Compression> {
+        &self.entries
+    }
+}
+
+pub struct Compression {
+    id: CompressionId,
+    description: String,
+}
+
+impl Compression {
+    pub fn id(&self) -> CompressionId {
+        self.id
+    }
+
+    pub fn description(&self) -> &str {
+        &self.description
+    }
+}
+
+// A Moa GUID identifying a compression type.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct CompressionId(pub u32, pub u16, pub u16, pub [u8; 8]);
+
+impl CompressionId {
+    pub const ZLIB: CompressionId = CompressionId(
+        0xAC99E904, 0x0070, 0x0B36, [0x00, 0x00, 0x08, 0x00, 0x07, 0x37, 0x7A, 0x34]
+    );
+
+    pub const NONE: CompressionId = CompressionId(
+        0xAC99982E, 0x005D, 0x0D50, [0x00, 0x00, 0x08, 0x00, 0x07, 0x37, 0x7A, 0x34]
+    );
+
+    pub const SOUND: CompressionId = CompressionId(
+        0x7204A889, 0xAFD0, 0x11CF, [0xA2, 0x22, 0x00, 0xA0, 0x24, 0x53, 0x44, 0x4C]
+    );
+
+    pub const FONT_MAP: CompressionId = CompressionId(
+        0x8A4679A1, 0x3720, 0x11D0, [0x92, 0x23, 0x00, 0xA0, 0xC9, 0x08, 0x68, 0xB1]
+    );
+}
+
+pub fn read_fcdr<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<CompressionTable, DirpError> {
+    super::read_fourcc::<R, E>(file, b"Fcdr")?;
+
+    let size = file.read_varint()?;
+
+    let mut data = vec![0; size as usize];
+    file.read_exact(&mut data)?;
+
+    let mut data = Cursor::new(super::decompress(&data)?);
+
+    let count = data.read_u16::<E>()?;
+
+    let mut ids = Vec::new();
+
+    for _ in 0..count {
+        let a = data.read_u32::<E>()?;
+        let b = data.read_u16::<E>()?;
+        let c = data.read_u16::<E>()?;
+        let mut d = [0; 8];
+        data.read_exact(&mut d)?;
+
+        ids.push(CompressionId(a, b, c, d));
+    }
+
+    let mut entries = Vec::new();
+
+    for id in ids {
+        let description = data.read_c_string()?;
+
+        entries.push(Compression {
+            id,
+            description,
+        });
+    }
+
+    Ok(CompressionTable {
+        entries,
+    })
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+
+use crate::endian::{Endian, Endianness};
+use crate::error::DirpError;
+
+use super::abmp::AfterburnerMap;
+
+// The resource ID of the initial load segment in the Afterburner map.
+const ILS_ID: u32 = 2;
+
+// The FGEI chunk holds the initial load segment of an Afterburner movie,
+// which is a compressed bundle of the chunks needed to start the movie.
+// The data of every other chunk follows it.
+pub struct InitialLoadSegment {
+    body_offset: u64,
+    chunks: HashMap<u32, Vec<u8>>,
+}
+
+impl InitialLoadSegment {
+    // The offset in the file that the Afterburner map offsets are relative to.
+    pub fn body_offset(&self) -> u64 {
+        self.body_offset
+    }
+
+    // The decompressed chunks in the segment, by resource ID.
+    pub fn chunks(&self) -> &HashMap<u32, Vec<u8>> {
+        &self.chunks
+    }
+
+    pub fn into_chunks(self) -> HashMap<u32, Vec<u8>> {
+        self.chunks
+    }
+}
+
+pub fn read_fgei<R: Read + Seek + Endian, E: Endianness>(
+    file: &mut R,
+    abmp: &AfterburnerMap
+) -> Result<InitialLoadSegment, DirpError> {
+    super::read_fourcc::<R, E>(file, b"FGEI")?;
+
+    let ils = abmp.entry(ILS_ID)?;
+
+    file.read_varint()?;
+
+    let body_offset = file.stream_position()?;
+
+    let mut data = vec![0; ils.compressed_size() as usize];
+    file.read_exact(&mut data)?;
+
+    let data = super::decompress(&data)?;
+    let len = data.len() as u64;
+    let mut data = Cursor::new(data);
+
+    let mut chunks = HashMap::new();
+
+    while data.position() < len {
+        let id = data.read_varint()?;
+        let size = abmp.entry(id)?.compressed_size();
+
+        let mut chunk = vec![0; size as usize];
+        data.read_exact(&mut chunk)?;
+
+        chunks.insert(id, chunk);
+    }
+
+    Ok(InitialLoadSegment {
+        body_offset,
+        chunks,
+    })
+}
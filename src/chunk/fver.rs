@@ -0,0 +1,62 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::endian::{Endian, Endianness};
+use crate::error::DirpError;
+
+// The Fver chunk starts an Afterburner movie.
+pub struct FileVersion {
+    version: u32,
+    imap_version: u32,
+    director_version: u32,
+    version_string: String,
+}
+
+impl FileVersion {
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    pub fn imap_version(&self) -> u32 {
+        self.imap_version
+    }
+
+    pub fn director_version(&self) -> u32 {
+        self.director_version
+    }
+
+    pub fn version_string(&self) -> &str {
+        &self.version_string
+    }
+}
+
+pub fn read_fver<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<FileVersion, DirpError> {
+    super::read_fourcc::<R, E>(file, b"Fver")?;
+
+    let size = file.read_varint()?;
+    let start = file.stream_position()?;
+
+    let version = file.read_varint()?;
+
+    let mut imap_version = 0;
+    let mut director_version = 0;
+    let mut version_string = String::new();
+
+    if version >= 0x401 {
+        imap_version = file.read_varint()?;
+        director_version = file.read_varint()?;
+    }
+
+    if version >= 0x501 {
+        version_string = file.read_pascal_string()?;
+    }
+
+    // Newer versions may have added fields we don't know about.
+    file.seek(SeekFrom::Start(start + size as u64))?;
+
+    Ok(FileVersion {
+        version,
+        imap_version,
+        director_version,
+        version_string,
+    })
+}
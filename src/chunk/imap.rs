@@ -1,6 +1,7 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
 use crate::endian::{Endian, Endianness};
+use crate::error::DirpError;
 
 pub struct InitialMap {
     memory_map_offset: u32,
@@ -12,22 +13,18 @@ impl InitialMap {
     }
 }
 
-pub fn read_imap<R: Read + Endian, E: Endianness>(file: &mut R) -> InitialMap {
-    let mut imap = [0; 4];
-    file.read_bytes::<E>(&mut imap);
-    if imap != [b'i', b'm', b'a', b'p'] {
-        panic!("Chunk header was incorrect");
-    }
+pub fn read_imap<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<InitialMap, DirpError> {
+    super::read_fourcc::<R, E>(file, b"imap")?;
 
-    let _size = file.read_u32::<E>();
+    let _size = file.read_u32::<E>()?;
 
-    let count = file.read_u32::<E>();
+    let count = file.read_u32::<E>()?;
 
     debug_assert_eq!(count, 1);
 
-    let offset = file.read_u32::<E>();
+    let offset = file.read_u32::<E>()?;
 
-    InitialMap {
+    Ok(InitialMap {
         memory_map_offset: offset,
-    }
+    })
 }
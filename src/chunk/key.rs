@@ -1,63 +1,98 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
 use crate::endian::{Endian, Endianness};
+use crate::error::DirpError;
 
 pub struct KeyTable {
     keys: Vec<Key>,
 }
 
 impl KeyTable {
+    pub fn keys(&self) -> &Vec<Key> {
+        &self.keys
+    }
+
     // Returns the resource ID for the first 'chunk' with 'owner'.
-    pub fn lookup(&self, owner: u32, chunk: String) -> Option<u32> {
-        for key in &self.keys {
-            if key.owner == owner && key.chunk == chunk {
-                return Some(key.owned);
-            }
-        }
+    pub fn lookup(&self, owner: u32, chunk: &[u8; 4]) -> Option<u32> {
+        self.lookup_all(owner, chunk).next()
+    }
+
+    // Returns the resource IDs for every 'chunk' with 'owner'.
+    pub fn lookup_all<'a>(
+        &'a self,
+        owner: u32,
+        chunk: &'a [u8; 4]
+    ) -> impl Iterator<Item = u32> + 'a {
+        self.owned_by(owner)
+            .filter(move |key| &key.chunk == chunk)
+            .map(|key| key.owned)
+    }
+
+    // Returns the keys of every chunk with 'owner'.
+    pub fn owned_by(&self, owner: u32) -> impl Iterator<Item = &Key> {
+        self.keys.iter().filter(move |key| key.owner == owner)
+    }
 
-        None
+    // Returns the resource ID of the owner of 'owned'.
+    pub fn owner(&self, owned: u32) -> Option<u32> {
+        self.keys.iter()
+            .find(|key| key.owned == owned)
+            .map(|key| key.owner)
     }
 }
 
 pub struct Key {
     owned: u32,
     owner: u32,
-    chunk: String,
+    chunk: [u8; 4],
 }
 
-pub fn read_key<R: Read + Endian, E: Endianness>(file: &mut R) -> KeyTable {
-    let mut key = [0; 4];
-    file.read_bytes::<E>(&mut key);
-    if key != [b'K', b'E', b'Y', b'*'] {
-        panic!("Chunk header was incorrect");
+impl Key {
+    // The resource ID of the owned chunk.
+    pub fn owned(&self) -> u32 {
+        self.owned
     }
 
-    let _size = file.read_u32::<E>();
+    // The resource ID of the owner, or the ID of a cast library
+    // for chunks that belong to a whole cast.
+    pub fn owner(&self) -> u32 {
+        self.owner
+    }
+
+    // The FourCC of the owned chunk.
+    pub fn chunk(&self) -> &[u8; 4] {
+        &self.chunk
+    }
+}
 
-    file.read_u16::<E>();
-    file.read_u16::<E>();
+pub fn read_key<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<KeyTable, DirpError> {
+    super::read_fourcc::<R, E>(file, b"KEY*")?;
+
+    let _size = file.read_u32::<E>()?;
+
+    file.read_u16::<E>()?;
+    file.read_u16::<E>()?;
 
     // NOTE Schokky parse 'max' number of keys instead of 'used'
-    let _max_key_count = file.read_u32::<E>();
-    let used_key_count = file.read_u32::<E>();
+    let _max_key_count = file.read_u32::<E>()?;
+    let used_key_count = file.read_u32::<E>()?;
 
     let mut keys = Vec::new();
 
     for _ in 0..used_key_count {
-        let owned = file.read_u32::<E>();
-        let owner = file.read_u32::<E>();
+        let owned = file.read_u32::<E>()?;
+        let owner = file.read_u32::<E>()?;
         let mut chunk = [0; 4];
-        file.read_bytes::<E>(&mut chunk);
-        let chunk = String::from_utf8(Vec::from(chunk)).unwrap();
+        file.read_bytes::<E>(&mut chunk)?;
 
         keys.push(Key {
-            owned: owned,
-            owner: owner,
-            chunk: chunk,
+            owned,
+            owner,
+            chunk,
         });
     }
 
-    KeyTable {
-        keys: keys,
-    }
+    Ok(KeyTable {
+        keys,
+    })
 }
@@ -0,0 +1,104 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The LctX (or Lctx) chunk is the script context of a cast library. It maps
+// the script numbers used by the cast members to the resource IDs of their
+// Lscr chunks, and points to the Lnam chunk that holds their names.
+pub struct ScriptContext {
+    capital_x: bool,
+    lnam_id: i32,
+    flags: u16,
+    sections: Vec<i32>,
+}
+
+impl ScriptContext {
+    // Whether the chunk is an LctX chunk, as opposed to an Lctx chunk.
+    pub fn capital_x(&self) -> bool {
+        self.capital_x
+    }
+
+    // The resource ID of the Lnam chunk.
+    pub fn lnam_id(&self) -> i32 {
+        self.lnam_id
+    }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    // The resource ID of the Lscr chunk of every script number,
+    // starting from 1, or -1 for unused script numbers.
+    pub fn sections(&self) -> &Vec<i32> {
+        &self.sections
+    }
+
+    // Returns the resource ID of the Lscr chunk of the script 'number'.
+    pub fn script_id(&self, number: u32) -> Option<u32> {
+        let index = (number as usize).checked_sub(1)?;
+
+        match self.sections.get(index) {
+            Some(&id) if id >= 0 => Some(id as u32),
+            _ => None,
+        }
+    }
+
+    // Returns the script numbers and Lscr resource IDs of every script.
+    pub fn scripts(&self) -> impl Iterator<Item = (u32, u32)> + '_ {
+        self.sections.iter()
+            .enumerate()
+            .filter(|(_, &id)| id >= 0)
+            .map(|(index, &id)| (index as u32 + 1, id as u32))
+    }
+}
+
+// The body of a script context is always big endian, no matter the
+// endianness of the movie. 'chunk' is either LctX or Lctx.
+pub fn read_lctx<R: Read + Seek + Endian, E: Endianness>(
+    file: &mut R,
+    chunk: &[u8; 4]
+) -> Result<ScriptContext, DirpError> {
+    super::read_fourcc::<R, E>(file, chunk)?;
+
+    let _size = file.read_u32::<E>()?;
+
+    // The offsets are from the start of the body
+    let start = file.stream_position()?;
+
+    let _unknown = file.read_i32::<BigEndian>()?;
+    let _unknown = file.read_i32::<BigEndian>()?;
+    let entry_count = file.read_u32::<BigEndian>()?;
+    let _entry_count = file.read_u32::<BigEndian>()?;
+    let entries_offset = file.read_u16::<BigEndian>()?;
+    let _unknown = file.read_i16::<BigEndian>()?;
+    let _unknown = file.read_i32::<BigEndian>()?;
+    let _unknown = file.read_i32::<BigEndian>()?;
+    let _unknown = file.read_i32::<BigEndian>()?;
+    let lnam_id = file.read_i32::<BigEndian>()?;
+    let _valid_count = file.read_u16::<BigEndian>()?;
+    let flags = file.read_u16::<BigEndian>()?;
+    let _free_pointer = file.read_i16::<BigEndian>()?;
+
+    log::trace!("Script context: {} entries, Lnam {}", entry_count, lnam_id);
+
+    file.seek(SeekFrom::Start(start + entries_offset as u64))?;
+
+    let mut sections = Vec::new();
+
+    for _ in 0..entry_count {
+        let _unknown = file.read_i32::<BigEndian>()?;
+        let id = file.read_i32::<BigEndian>()?;
+        let _unknown = file.read_u16::<BigEndian>()?;
+        let _unknown = file.read_u16::<BigEndian>()?;
+
+        sections.push(id);
+    }
+
+    Ok(ScriptContext {
+        capital_x: chunk == b"LctX",
+        lnam_id,
+        flags,
+        sections,
+    })
+}
@@ -0,0 +1,71 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The Lnam chunk holds the names of the handlers, variables and symbols
+// used by the scripts of a script context. Scripts refer to them by index.
+pub struct NameTable {
+    names: Vec<String>,
+}
+
+impl NameTable {
+    pub fn names(&self) -> &Vec<String> {
+        &self.names
+    }
+
+    // Returns the name with the name ID 'id'.
+    pub fn name(&self, id: i16) -> Option<&str> {
+        if id < 0 {
+            return None;
+        }
+
+        self.names.get(id as usize).map(|name| name.as_str())
+    }
+}
+
+#[cfg(test)]
+impl NameTable {
+    pub(crate) fn new(names: &[&str]) -> NameTable {
+        NameTable {
+            names: names.iter().map(|name| name.to_string()).collect(),
+        }
+    }
+}
+
+// The body of a name table is always big endian,
+// no matter the endianness of the movie.
+pub fn read_lnam<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<NameTable, DirpError> {
+    super::read_fourcc::<R, E>(file, b"Lnam")?;
+
+    let _size = file.read_u32::<E>()?;
+
+    // The offsets are from the start of the body
+    let start = file.stream_position()?;
+
+    let _unknown = file.read_i32::<BigEndian>()?;
+    let _unknown = file.read_i32::<BigEndian>()?;
+    let _len = file.read_u32::<BigEndian>()?;
+    let _len = file.read_u32::<BigEndian>()?;
+    let names_offset = file.read_u16::<BigEndian>()?;
+    let names_count = file.read_u16::<BigEndian>()?;
+
+    file.seek(SeekFrom::Start(start + names_offset as u64))?;
+
+    let mut names = Vec::with_capacity(names_count as usize);
+
+    for _ in 0..names_count {
+        // NOTE Names are usually ASCII, but may be Mac Roman,
+        // so invalid bytes are replaced.
+        let len = file.read_u8()? as usize;
+
+        let mut bytes = vec![0; len];
+        file.read_exact(&mut bytes)?;
+
+        names.push(String::from_utf8_lossy(&bytes).into_owned());
+    }
+
+    Ok(NameTable {
+        names,
+    })
+}
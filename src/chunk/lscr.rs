@@ -0,0 +1,395 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The Lscr chunk holds a compiled Lingo script. Names are stored as IDs
+// into the name table (Lnam) of the script context that the script is in.
+#[derive(Debug)]
+pub struct Script {
+    version: u32,
+    capital_x: bool,
+    number: u16,
+    parent_number: i16,
+    flags: u32,
+    cast_id: i32,
+    factory_name_id: i16,
+    properties: Vec<i16>,
+    globals: Vec<i16>,
+    handlers: Vec<Handler>,
+    literals: Vec<Literal>,
+}
+
+impl Script {
+    // The version of Director that compiled the script, as in 850 for 8.5.
+    pub fn version(&self) -> u32 {
+        self.version
+    }
+
+    // Whether the script is in an LctX script context, as opposed to an Lctx
+    // one. The bytecode of these scripts refers to arguments, locals and
+    // literals by their index rather than their byte offset.
+    pub fn capital_x(&self) -> bool {
+        self.capital_x
+    }
+
+    // The number of the script in its script context, starting from 1.
+    pub fn number(&self) -> u16 {
+        self.number
+    }
+
+    pub fn parent_number(&self) -> i16 {
+        self.parent_number
+    }
+
+    pub fn flags(&self) -> u32 {
+        self.flags
+    }
+
+    // The cast library (upper 16 bits) and member number (lower 16 bits)
+    // of the cast member that the script belongs to.
+    pub fn cast_id(&self) -> i32 {
+        self.cast_id
+    }
+
+    // The name ID of the factory, for scripts that define one.
+    pub fn factory_name_id(&self) -> i16 {
+        self.factory_name_id
+    }
+
+    // The name IDs of the properties declared by the script.
+    pub fn properties(&self) -> &Vec<i16> {
+        &self.properties
+    }
+
+    // The name IDs of the globals declared by the script.
+    pub fn globals(&self) -> &Vec<i16> {
+        &self.globals
+    }
+
+    pub fn handlers(&self) -> &Vec<Handler> {
+        &self.handlers
+    }
+
+    // The constants used by the handlers, which the bytecode
+    // refers to by their index.
+    pub fn literals(&self) -> &Vec<Literal> {
+        &self.literals
+    }
+}
+
+// Scripts built by hand for the tests of the disassembler and decompiler.
+#[cfg(test)]
+impl Script {
+    pub(crate) fn new(
+        version: u32,
+        capital_x: bool,
+        handlers: Vec<Handler>,
+        literals: Vec<Literal>
+    ) -> Script {
+        Script {
+            version,
+            capital_x,
+            number: 1,
+            parent_number: -1,
+            flags: 0,
+            cast_id: 0,
+            factory_name_id: -1,
+            properties: Vec::new(),
+            globals: Vec::new(),
+            handlers,
+            literals,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Handler {
+    name_id: i16,
+    vector_pos: u16,
+    arguments: Vec<i16>,
+    locals: Vec<i16>,
+    globals: Vec<i16>,
+    bytecode: Vec<u8>,
+    stack_height: Option<u32>,
+}
+
+impl Handler {
+    pub fn name_id(&self) -> i16 {
+        self.name_id
+    }
+
+    pub fn vector_pos(&self) -> u16 {
+        self.vector_pos
+    }
+
+    // The name IDs of the arguments of the handler.
+    pub fn arguments(&self) -> &Vec<i16> {
+        &self.arguments
+    }
+
+    // The name IDs of the local variables of the handler.
+    pub fn locals(&self) -> &Vec<i16> {
+        &self.locals
+    }
+
+    // The name IDs of the globals used by the handler.
+    pub fn globals(&self) -> &Vec<i16> {
+        &self.globals
+    }
+
+    // The compiled Lingo bytecode of the handler.
+    pub fn bytecode(&self) -> &Vec<u8> {
+        &self.bytecode
+    }
+
+    // Only scripts in an LctX script context store the stack height.
+    pub fn stack_height(&self) -> Option<u32> {
+        self.stack_height
+    }
+}
+
+#[cfg(test)]
+impl Handler {
+    pub(crate) fn new(name_id: i16, arguments: Vec<i16>, locals: Vec<i16>, bytecode: Vec<u8>) -> Handler {
+        Handler {
+            name_id,
+            vector_pos: 0,
+            arguments,
+            locals,
+            globals: Vec::new(),
+            bytecode,
+            stack_height: None,
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Literal {
+    String(String),
+    Int(i32),
+    Float(f64),
+    // A literal of a type we don't know, with the type and its data.
+    Unknown(u32, Vec<u8>),
+}
+
+// The body of an Lscr chunk is always big endian, no matter the endianness
+// of the movie. 'capital_x' is whether the script context is an LctX
+// chunk, as opposed to an Lctx chunk, which changes the size of the
+// handler records. 'version' is the version of Director, as in 850 for 8.5.
+pub fn read_lscr<R: Read + Seek + Endian, E: Endianness>(
+    file: &mut R,
+    capital_x: bool,
+    version: u32
+) -> Result<Script, DirpError> {
+    super::read_fourcc::<R, E>(file, b"Lscr")?;
+
+    let size = file.read_u32::<E>()?;
+
+    // All offsets are from the start of the body,
+    // so it is read on its own.
+    let mut body = vec![0; size as usize];
+    file.read_exact(&mut body)?;
+
+    let mut file = Cursor::new(&body[..]);
+
+    file.seek(SeekFrom::Start(18))?;
+    let number = file.read_u16::<BigEndian>()?;
+    let _unknown = file.read_i16::<BigEndian>()?;
+    let parent_number = file.read_i16::<BigEndian>()?;
+
+    file.seek(SeekFrom::Start(38))?;
+    let flags = file.read_u32::<BigEndian>()?;
+    let _unknown = file.read_i16::<BigEndian>()?;
+    let cast_id = file.read_i32::<BigEndian>()?;
+    let factory_name_id = file.read_i16::<BigEndian>()?;
+
+    let _handler_vectors_count = file.read_u16::<BigEndian>()?;
+    let _handler_vectors_offset = file.read_u32::<BigEndian>()?;
+    let _handler_vectors_size = file.read_u32::<BigEndian>()?;
+
+    let properties_count = file.read_u16::<BigEndian>()?;
+    let properties_offset = file.read_u32::<BigEndian>()?;
+    let globals_count = file.read_u16::<BigEndian>()?;
+    let globals_offset = file.read_u32::<BigEndian>()?;
+    let handlers_count = file.read_u16::<BigEndian>()?;
+    let handlers_offset = file.read_u32::<BigEndian>()?;
+    let literals_count = file.read_u16::<BigEndian>()?;
+    let literals_offset = file.read_u32::<BigEndian>()?;
+    let _literals_data_size = file.read_u32::<BigEndian>()?;
+    let literals_data_offset = file.read_u32::<BigEndian>()?;
+
+    log::trace!(
+        "Script {}: {} handlers, {} literals",
+        number, handlers_count, literals_count
+    );
+
+    let properties = read_name_ids(&mut file, properties_offset, properties_count)?;
+    let globals = read_name_ids(&mut file, globals_offset, globals_count)?;
+
+    file.seek(SeekFrom::Start(handlers_offset as u64))?;
+
+    let mut records = Vec::new();
+
+    for _ in 0..handlers_count {
+        records.push(read_handler_record(&mut file, capital_x)?);
+    }
+
+    let mut handlers = Vec::new();
+
+    for record in records {
+        let start = record.compiled_offset as usize;
+        let end = start + record.compiled_len as usize;
+
+        let bytecode = body.get(start..end).ok_or(DirpError::Truncated)?.to_vec();
+
+        handlers.push(Handler {
+            name_id: record.name_id,
+            vector_pos: record.vector_pos,
+            arguments: read_name_ids(&mut file, record.arguments_offset, record.arguments_count)?,
+            locals: read_name_ids(&mut file, record.locals_offset, record.locals_count)?,
+            globals: read_name_ids(&mut file, record.globals_offset, record.globals_count)?,
+            bytecode,
+            stack_height: record.stack_height,
+        });
+    }
+
+    file.seek(SeekFrom::Start(literals_offset as u64))?;
+
+    let mut records = Vec::new();
+
+    for _ in 0..literals_count {
+        // Director 4 stores the type as a u16
+        let kind = if version >= 500 {
+            file.read_u32::<BigEndian>()?
+        } else {
+            file.read_u16::<BigEndian>()? as u32
+        };
+        let offset = file.read_u32::<BigEndian>()?;
+
+        records.push((kind, offset));
+    }
+
+    let mut literals = Vec::new();
+
+    for (kind, offset) in records {
+        let literal = match kind {
+            // The value of an integer is stored in place of the offset
+            4 => Literal::Int(offset as i32),
+            kind => {
+                file.seek(SeekFrom::Start(literals_data_offset as u64 + offset as u64))?;
+
+                let len = file.read_u32::<BigEndian>()? as usize;
+
+                match (kind, len) {
+                    // Strings include a terminating NULL byte. They are often
+                    // Mac Roman rather than UTF-8, so invalid bytes are replaced.
+                    (1, len) => {
+                        let mut bytes = vec![0; len];
+                        file.read_exact(&mut bytes)?;
+
+                        let string = String::from_utf8_lossy(&bytes);
+
+                        Literal::String(string.trim_end_matches('\0').to_string())
+                    },
+                    (9, 8) => Literal::Float(file.read_f64::<BigEndian>()?),
+                    (9, 10) => Literal::Float(file.read_extended::<BigEndian>()?),
+                    (kind, len) => {
+                        let mut data = vec![0; len];
+                        file.read_exact(&mut data)?;
+
+                        Literal::Unknown(kind, data)
+                    },
+                }
+            },
+        };
+
+        literals.push(literal);
+    }
+
+    Ok(Script {
+        version,
+        capital_x,
+        number,
+        parent_number,
+        flags,
+        cast_id,
+        factory_name_id,
+        properties,
+        globals,
+        handlers,
+        literals,
+    })
+}
+
+struct HandlerRecord {
+    name_id: i16,
+    vector_pos: u16,
+    compiled_len: u32,
+    compiled_offset: u32,
+    arguments_count: u16,
+    arguments_offset: u32,
+    locals_count: u16,
+    locals_offset: u32,
+    globals_count: u16,
+    globals_offset: u32,
+    stack_height: Option<u32>,
+}
+
+fn read_handler_record<R: Read + Endian>(
+    file: &mut R,
+    capital_x: bool
+) -> Result<HandlerRecord, DirpError> {
+    let name_id = file.read_i16::<BigEndian>()?;
+    let vector_pos = file.read_u16::<BigEndian>()?;
+    let compiled_len = file.read_u32::<BigEndian>()?;
+    let compiled_offset = file.read_u32::<BigEndian>()?;
+    let arguments_count = file.read_u16::<BigEndian>()?;
+    let arguments_offset = file.read_u32::<BigEndian>()?;
+    let locals_count = file.read_u16::<BigEndian>()?;
+    let locals_offset = file.read_u32::<BigEndian>()?;
+    let globals_count = file.read_u16::<BigEndian>()?;
+    let globals_offset = file.read_u32::<BigEndian>()?;
+    let _unknown = file.read_u32::<BigEndian>()?;
+    let _unknown = file.read_u16::<BigEndian>()?;
+    // The line numbers of the handler, which aren't parsed
+    let _line_count = file.read_u16::<BigEndian>()?;
+    let _line_offset = file.read_u32::<BigEndian>()?;
+
+    let stack_height = if capital_x {
+        Some(file.read_u32::<BigEndian>()?)
+    } else {
+        None
+    };
+
+    Ok(HandlerRecord {
+        name_id,
+        vector_pos,
+        compiled_len,
+        compiled_offset,
+        arguments_count,
+        arguments_offset,
+        locals_count,
+        locals_offset,
+        globals_count,
+        globals_offset,
+        stack_height,
+    })
+}
+
+// Read 'count' name IDs at 'offset'.
+fn read_name_ids<R: Read + Seek + Endian>(
+    file: &mut R,
+    offset: u32,
+    count: u16
+) -> Result<Vec<i16>, DirpError> {
+    file.seek(SeekFrom::Start(offset as u64))?;
+
+    let mut ids = Vec::with_capacity(count as usize);
+
+    for _ in 0..count {
+        ids.push(file.read_i16::<BigEndian>()?);
+    }
+
+    Ok(ids)
+}
@@ -1,6 +1,7 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
 use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
 
 #[derive(Clone)]
 pub struct MovieCastList {
@@ -8,6 +9,21 @@ pub struct MovieCastList {
 }
 
 impl MovieCastList {
+    // The cast list of a movie that doesn't have a MCsL chunk,
+    // which only has the internal cast.
+    pub(crate) fn internal() -> MovieCastList {
+        MovieCastList {
+            entries: vec![Cast {
+                name: "Internal".to_string(),
+                path: String::new(),
+                min: 1,
+                max: 1,
+                member_count: 0,
+                id: 0x400,
+            }],
+        }
+    }
+
     pub fn entries(&self) -> &Vec<Cast> {
         &self.entries
     }
@@ -32,70 +48,86 @@ impl Cast {
         &self.path
     }
 
+    pub fn min(&self) -> u8 {
+        self.min
+    }
+
+    pub fn max(&self) -> u8 {
+        self.max
+    }
+
+    pub fn member_count(&self) -> u16 {
+        self.member_count
+    }
+
     pub fn id(&self) -> u32 {
         self.id
     }
 }
 
-pub fn read_mcsl<R: Read + Endian, E: Endianness>(file: &mut R) -> MovieCastList {
-    let mut mcsl = [0; 4];
-    file.read_bytes::<E>(&mut mcsl);
-    if mcsl != [b'M', b'C', b's', b'L'] {
-        panic!("Chunk header was incorrect");
+#[cfg(test)]
+impl Cast {
+    pub(crate) fn new(name: &str, path: &str) -> Cast {
+        Cast {
+            name: name.to_string(),
+            path: path.to_string(),
+            min: 1,
+            max: 1,
+            member_count: 0,
+            id: 1,
+        }
     }
+}
 
-    let _size = file.read_u32::<E>();
+pub fn read_mcsl<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<MovieCastList, DirpError> {
+    super::read_fourcc::<R, E>(file, b"MCsL")?;
 
-    file.read_u32::<BigEndian>();
+    let _size = file.read_u32::<E>()?;
 
-    let count = file.read_u32::<BigEndian>();
+    file.read_u32::<BigEndian>()?;
 
-    file.read_u16::<BigEndian>();
+    let count = file.read_u32::<BigEndian>()?;
 
-    let x = file.read_u32::<BigEndian>();
+    file.read_u16::<BigEndian>()?;
+
+    let x = file.read_u32::<BigEndian>()?;
     for _ in 0..x {
-        file.read_u32::<BigEndian>();
+        file.read_u32::<BigEndian>()?;
     }
 
-    file.read_u32::<BigEndian>();
+    file.read_u32::<BigEndian>()?;
 
     let mut entries = Vec::new();
 
     for _ in 0..count {
-        let len = file.read_u8();
-        let mut name = vec![0; len as usize];
-        file.read_bytes::<BigEndian>(&mut name);
-        let name = String::from_utf8(name).unwrap();
-        file.read_u8();
-
-        let len = file.read_u8();
-        let mut path = vec![0; len as usize];
-        file.read_bytes::<BigEndian>(&mut path);
-        let path = String::from_utf8(path).unwrap();
-        file.read_u8();
+        let name = file.read_pascal_string()?;
+        file.read_u8()?;
+
+        let path = file.read_pascal_string()?;
+        file.read_u8()?;
 
         if !path.is_empty() {
-            file.read_u8();
+            file.read_u8()?;
         }
 
-        let min = file.read_u8();
-        let max = file.read_u8();
+        let min = file.read_u8()?;
+        let max = file.read_u8()?;
 
-        let member_count = file.read_u16::<BigEndian>();
+        let member_count = file.read_u16::<BigEndian>()?;
 
-        let id = file.read_u32::<BigEndian>();
+        let id = file.read_u32::<BigEndian>()?;
 
         entries.push(Cast {
-            name: name,
-            path: path,
-            min: min,
-            max: max,
-            member_count: member_count,
-            id: id,
+            name,
+            path,
+            min,
+            max,
+            member_count,
+            id,
         });
     }
 
-    MovieCastList {
-        entries: entries,
-    }
+    Ok(MovieCastList {
+        entries,
+    })
 }
@@ -1,24 +1,80 @@
-use std::io::Read;
+use std::io::{Read, Seek};
 
 use crate::endian::{Endian, Endianness};
+use crate::error::DirpError;
 
 pub struct MemoryMap {
     entries: Vec<MemoryMapEntry>,
+    junk_head: i32,
+    junk_head2: i32,
+    free_head: i32,
 }
 
 impl MemoryMap {
+    pub(crate) fn new(entries: Vec<MemoryMapEntry>) -> MemoryMap {
+        MemoryMap {
+            entries,
+            junk_head: -1,
+            junk_head2: -1,
+            free_head: -1,
+        }
+    }
+
     pub fn entries(&self) -> &Vec<MemoryMapEntry> {
         &self.entries
     }
+
+    // Returns the entry for the resource ID 'id'.
+    pub fn entry(&self, id: u32) -> Result<&MemoryMapEntry, DirpError> {
+        self.entries.get(id as usize).ok_or(DirpError::MissingChunk(id))
+    }
+
+    // The resource ID of the first junk entry.
+    pub fn junk_head(&self) -> Option<u32> {
+        link(self.junk_head)
+    }
+
+    // NOTE This is usually the same as the junk head,
+    // but its purpose is unknown.
+    pub fn junk_head2(&self) -> Option<u32> {
+        link(self.junk_head2)
+    }
+
+    // The resource ID of the first free entry.
+    pub fn free_head(&self) -> Option<u32> {
+        link(self.free_head)
+    }
+
+    // Iterate over the resource IDs and entries that aren't free or junk.
+    pub fn live_entries(&self) -> impl Iterator<Item = (u32, &MemoryMapEntry)> {
+        self.entries.iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.is_free() && !entry.is_junk())
+            .map(|(id, entry)| (id as u32, entry))
+    }
 }
 
 pub struct MemoryMapEntry {
     chunk: String,
     size: u32,
     offset: u32,
+    flags: u16,
+    unknown: u16,
+    next: i32,
 }
 
 impl MemoryMapEntry {
+    pub(crate) fn new(chunk: String, size: u32, offset: u32) -> MemoryMapEntry {
+        MemoryMapEntry {
+            chunk,
+            size,
+            offset,
+            flags: 0,
+            unknown: 0,
+            next: -1,
+        }
+    }
+
     pub fn chunk(&self) -> &str {
         &self.chunk
     }
@@ -30,52 +86,87 @@ impl MemoryMapEntry {
     pub fn offset(&self) -> u32 {
         self.offset
     }
+
+    pub fn flags(&self) -> u16 {
+        self.flags
+    }
+
+    pub fn unknown(&self) -> u16 {
+        self.unknown
+    }
+
+    // The resource ID of the next entry in the free or junk list.
+    pub fn next(&self) -> Option<u32> {
+        link(self.next)
+    }
+
+    // Free entries are unused slots in the memory map. They may still
+    // point at the data of a deleted chunk.
+    pub fn is_free(&self) -> bool {
+        self.chunk == "free"
+    }
+
+    // Junk entries point at data that is no longer used by the movie.
+    pub fn is_junk(&self) -> bool {
+        self.chunk == "junk"
+    }
 }
 
-pub fn read_mmap<R: Read + Endian, E: Endianness>(file: &mut R) -> MemoryMap {
-    let mut mmap = [0; 4];
-    file.read_bytes::<E>(&mut mmap);
-    if mmap != [b'm', b'm', b'a', b'p'] {
-        panic!("Chunk header was incorrect");
+// Links between entries use -1 to mark the end of a list.
+fn link(id: i32) -> Option<u32> {
+    if id < 0 {
+        None
+    } else {
+        Some(id as u32)
     }
+}
+
+pub fn read_mmap<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<MemoryMap, DirpError> {
+    super::read_fourcc::<R, E>(file, b"mmap")?;
 
-    let _size = file.read_u32::<E>();
+    let _size = file.read_u32::<E>()?;
 
-    file.read_u16::<E>();
-    file.read_u16::<E>();
+    let _header_size = file.read_u16::<E>()?;
+    let _entry_size = file.read_u16::<E>()?;
 
-    let _chunk_count_max = file.read_u32::<E>();
-    let chunk_count_used = file.read_u32::<E>();
+    let _chunk_count_max = file.read_u32::<E>()?;
+    let chunk_count_used = file.read_u32::<E>()?;
 
-    file.read_u32::<E>();
-    file.read_u32::<E>();
-    file.read_u32::<E>();
+    let junk_head = file.read_i32::<E>()?;
+    let junk_head2 = file.read_i32::<E>()?;
+    let free_head = file.read_i32::<E>()?;
 
     let mut entries = Vec::new();
 
     for _ in 0..chunk_count_used {
         let mut chunk = [0; 4];
-        file.read_bytes::<E>(&mut chunk);
-        let chunk = String::from_utf8(Vec::from(chunk)).unwrap();
+        file.read_bytes::<E>(&mut chunk)?;
+        let chunk = String::from_utf8_lossy(&chunk).into_owned();
 
-        let size = file.read_u32::<E>();
+        let size = file.read_u32::<E>()?;
 
-        let offset = file.read_u32::<E>();
+        let offset = file.read_u32::<E>()?;
 
-        file.read_u16::<E>();
-        file.read_u16::<E>();
-        file.read_u32::<E>();
+        let flags = file.read_u16::<E>()?;
+        let unknown = file.read_u16::<E>()?;
+        let next = file.read_i32::<E>()?;
 
         let entry = MemoryMapEntry {
-            chunk: chunk,
-            size: size,
-            offset: offset,
+            chunk,
+            size,
+            offset,
+            flags,
+            unknown,
+            next,
         };
 
         entries.push(entry);
     }
 
-    MemoryMap {
-        entries: entries,
-    }
+    Ok(MemoryMap {
+        entries,
+        junk_head,
+        junk_head2,
+        free_head,
+    })
 }
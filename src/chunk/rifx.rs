@@ -1,5 +1,7 @@
 use std::io::Read;
 
+use crate::error::DirpError;
+
 pub struct Header {
     endian: Endianness,
     size: u32,
@@ -11,26 +13,62 @@ pub enum Endianness {
     Big, Little,
 }
 
+// The kind of file, given by the codec in the header.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum MovieKind {
+    Movie,           // MV93, a dir/dxr file
+    Cast,            // MC95, a cst/cxt file
+    Projector,       // APPL
+    CompressedMovie, // FGDM, an Afterburner dcr file
+    CompressedCast,  // FGDC, an Afterburner cct file
+    Unknown,
+}
+
 impl Header {
     pub fn endian(&self) -> Endianness {
         self.endian
     }
+
+    // The size of the file, excluding the RIFX FourCC and size.
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn codec(&self) -> &str {
+        &self.codec
+    }
+
+    pub fn kind(&self) -> MovieKind {
+        match self.codec.as_str() {
+            "MV93" => MovieKind::Movie,
+            "MC95" => MovieKind::Cast,
+            "APPL" => MovieKind::Projector,
+            "FGDM" => MovieKind::CompressedMovie,
+            "FGDC" => MovieKind::CompressedCast,
+            _ => MovieKind::Unknown,
+        }
+    }
+
+    // Whether the movie is compressed with Afterburner.
+    pub fn is_afterburner(&self) -> bool {
+        matches!(self.kind(), MovieKind::CompressedMovie | MovieKind::CompressedCast)
+    }
 }
 
-pub fn read_rifx<R: Read>(file: &mut R) -> Header {
+pub fn read_rifx<R: Read>(file: &mut R) -> Result<Header, DirpError> {
     let mut rifx = [0; 4];
-    file.read(&mut rifx).unwrap();
+    file.read_exact(&mut rifx)?;
 
     let endian = if rifx == [b'R', b'I', b'F', b'X'] {
         Endianness::Big
     } else if rifx == [b'X', b'F', b'I', b'R'] {
         Endianness::Little
     } else {
-        panic!("Header did not start with RIFX or XFIR");
+        return Err(DirpError::InvalidHeader(rifx));
     };
 
     let mut size = [0; 4];
-    file.read(&mut size).unwrap();
+    file.read_exact(&mut size)?;
 
     let size = match endian {
         Endianness::Big    => u32::from_be_bytes(size),
@@ -38,22 +76,22 @@ pub fn read_rifx<R: Read>(file: &mut R) -> Header {
     };
 
     let mut codec = [0; 4];
-    file.read(&mut codec).unwrap();
+    file.read_exact(&mut codec)?;
 
     let codec = match endian {
         Endianness::Big    => {
-            String::from_utf8(Vec::from(codec)).unwrap()
+            String::from_utf8_lossy(&codec).into_owned()
         },
         Endianness::Little => {
             let mut v = Vec::from(codec);
             v.reverse();
-            String::from_utf8(v).unwrap()
+            String::from_utf8_lossy(&v).into_owned()
         },
     };
 
-    Header {
-        endian: endian,
-        size: size,
-        codec: codec,
-    }
+    Ok(Header {
+        endian,
+        size,
+        codec,
+    })
 }
@@ -0,0 +1,52 @@
+use std::io::{Read, Seek, SeekFrom};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The STXT chunk holds the text of a text or field cast member, and the
+// Lingo source of a script member in files that haven't been protected.
+pub struct StyledText {
+    text: String,
+    formatting: Vec<u8>,
+}
+
+impl StyledText {
+    // The text with its line endings converted from Mac to Unix.
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    // The style runs of the text, which aren't parsed.
+    pub fn formatting(&self) -> &Vec<u8> {
+        &self.formatting
+    }
+}
+
+// The body of an STXT chunk is always big endian.
+pub fn read_stxt<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<StyledText, DirpError> {
+    super::read_fourcc::<R, E>(file, b"STXT")?;
+
+    let _size = file.read_u32::<E>()?;
+    let start = file.stream_position()?;
+
+    let header_len = file.read_u32::<BigEndian>()?;
+    let text_len = file.read_u32::<BigEndian>()?;
+    let formatting_len = file.read_u32::<BigEndian>()?;
+
+    file.seek(SeekFrom::Start(start + header_len as u64))?;
+
+    let mut text = vec![0; text_len as usize];
+    file.read_bytes::<BigEndian>(&mut text)?;
+
+    let mut formatting = vec![0; formatting_len as usize];
+    file.read_bytes::<BigEndian>(&mut formatting)?;
+
+    // The text is often Mac Roman rather than UTF-8,
+    // so invalid bytes are replaced.
+    let text = String::from_utf8_lossy(&text).replace("\r\n", "\n").replace('\r', "\n");
+
+    Ok(StyledText {
+        text,
+        formatting,
+    })
+}
@@ -0,0 +1,506 @@
+use std::io::{Cursor, Read, Seek, SeekFrom};
+
+use crate::endian::{Endian, Endianness, BigEndian};
+use crate::error::DirpError;
+
+// The size of the sprite records that we know the layout of,
+// which is the one used from Director 5.
+const SPRITE_RECORD_SIZE: usize = 24;
+
+// The special channels come before the sprite channels
+// and take up the space of two sprite records.
+const MAIN_CHANNELS_SIZE: usize = 48;
+
+// The VWSC chunk holds the score of a movie. Every frame only stores
+// the bytes of the channels that changed since the frame before it.
+pub struct Score {
+    frames_version: u16,
+    sprite_record_size: u16,
+    channel_count: u16,
+    displayed_channel_count: u16,
+    frames: Vec<Frame>,
+}
+
+impl Score {
+    pub fn frames_version(&self) -> u16 {
+        self.frames_version
+    }
+
+    // The size of the record of every sprite channel, in bytes.
+    pub fn sprite_record_size(&self) -> u16 {
+        self.sprite_record_size
+    }
+
+    // The number of channels stored for every frame.
+    pub fn channel_count(&self) -> u16 {
+        self.channel_count
+    }
+
+    // The number of sprite channels shown in the score window.
+    pub fn displayed_channel_count(&self) -> u16 {
+        self.displayed_channel_count
+    }
+
+    pub fn frames(&self) -> &Vec<Frame> {
+        &self.frames
+    }
+
+    // Returns the frame with the number 'number', starting from 1.
+    pub fn frame(&self, number: usize) -> Option<&Frame> {
+        self.frames.get(number.checked_sub(1)?)
+    }
+}
+
+// A reference to a cast member from the score. Empty channels
+// refer to member zero.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct MemberRef {
+    pub cast: i16,
+    pub member: i16,
+}
+
+impl MemberRef {
+    pub fn is_empty(&self) -> bool {
+        self.member == 0
+    }
+}
+
+pub struct Frame {
+    script: MemberRef,
+    tempo: u8,
+    transition: MemberRef,
+    sound1: MemberRef,
+    sound2: MemberRef,
+    palette: MemberRef,
+    palette_speed: u8,
+    palette_flags: u8,
+    sprites: Vec<Sprite>,
+}
+
+impl Frame {
+    // The frame script, or an empty reference for frames without one.
+    pub fn script(&self) -> MemberRef {
+        self.script
+    }
+
+    // The tempo in frames per second, or zero to keep the current tempo.
+    pub fn tempo(&self) -> u8 {
+        self.tempo
+    }
+
+    pub fn transition(&self) -> MemberRef {
+        self.transition
+    }
+
+    pub fn sound1(&self) -> MemberRef {
+        self.sound1
+    }
+
+    pub fn sound2(&self) -> MemberRef {
+        self.sound2
+    }
+
+    // The palette cast member, or a member number of zero or less
+    // for a built-in palette.
+    pub fn palette(&self) -> MemberRef {
+        self.palette
+    }
+
+    pub fn palette_speed(&self) -> u8 {
+        self.palette_speed
+    }
+
+    pub fn palette_flags(&self) -> u8 {
+        self.palette_flags
+    }
+
+    // The sprite channels of the frame, starting with channel 1.
+    pub fn sprites(&self) -> &Vec<Sprite> {
+        &self.sprites
+    }
+
+    // Returns the sprite in the channel 'channel', starting from 1,
+    // unless the channel is empty.
+    pub fn sprite(&self, channel: usize) -> Option<&Sprite> {
+        self.sprites.get(channel.checked_sub(1)?)
+            .filter(|sprite| !sprite.is_empty())
+    }
+}
+
+#[derive(Debug)]
+pub struct Sprite {
+    kind: u8,
+    ink: u8,
+    member: MemberRef,
+    script: MemberRef,
+    fore_color: u8,
+    back_color: u8,
+    top: i16,
+    left: i16,
+    height: i16,
+    width: i16,
+    color_code: u8,
+    blend: u8,
+    line_size: u8,
+}
+
+impl Sprite {
+    // The sprite type, which is zero for an empty channel.
+    pub fn kind(&self) -> u8 {
+        self.kind
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.kind == 0 && self.member.is_empty()
+    }
+
+    // The ink, as in 0 for copy and 8 for matte.
+    pub fn ink(&self) -> u8 {
+        self.ink & 0x3f
+    }
+
+    pub fn trails(&self) -> bool {
+        self.ink & 0x40 != 0
+    }
+
+    pub fn member(&self) -> MemberRef {
+        self.member
+    }
+
+    // The behavior attached to the sprite.
+    pub fn script(&self) -> MemberRef {
+        self.script
+    }
+
+    pub fn fore_color(&self) -> u8 {
+        self.fore_color
+    }
+
+    pub fn back_color(&self) -> u8 {
+        self.back_color
+    }
+
+    // The location of the sprite on the stage as (x, y).
+    pub fn position(&self) -> (i16, i16) {
+        (self.left, self.top)
+    }
+
+    pub fn width(&self) -> i16 {
+        self.width
+    }
+
+    pub fn height(&self) -> i16 {
+        self.height
+    }
+
+    // The color of the sprite in the score window.
+    pub fn color_code(&self) -> u8 {
+        self.color_code
+    }
+
+    // NOTE This is the blend as stored, and how it maps to
+    // the blend percentage hasn't been confirmed yet.
+    pub fn blend(&self) -> u8 {
+        self.blend
+    }
+
+    pub fn line_size(&self) -> u8 {
+        self.line_size
+    }
+}
+
+// The body of the score is always big endian, no matter the
+// endianness of the movie.
+pub fn read_vwsc<R: Read + Seek + Endian, E: Endianness>(file: &mut R) -> Result<Score, DirpError> {
+    super::read_fourcc::<R, E>(file, b"VWSC")?;
+
+    let size = file.read_u32::<E>()?;
+
+    let mut body = vec![0; size as usize];
+    file.read_exact(&mut body)?;
+
+    let mut file = Cursor::new(&body[..]);
+
+    let _total_length = file.read_u32::<BigEndian>()?;
+    let marker = file.read_i32::<BigEndian>()?;
+
+    // From Director 6, the score is split into entries, with the
+    // frames in the first one. Before that it is just the frames.
+    let frames_offset = if marker == -3 {
+        let _unknown = file.read_u32::<BigEndian>()?;
+        let entry_count = file.read_u32::<BigEndian>()?;
+        let _unknown = file.read_u32::<BigEndian>()?;
+        let _entries_size = file.read_u32::<BigEndian>()?;
+
+        let first = file.read_u32::<BigEndian>()? as u64;
+
+        // The offsets are from the end of the offset table
+        24 + (entry_count as u64 + 1) * 4 + first
+    } else {
+        0
+    };
+
+    file.seek(SeekFrom::Start(frames_offset))?;
+
+    let frames_length = file.read_u32::<BigEndian>()? as u64;
+    let header_length = file.read_u32::<BigEndian>()? as u64;
+    let frame_count = file.read_u32::<BigEndian>()?;
+    let frames_version = file.read_u16::<BigEndian>()?;
+    let sprite_record_size = file.read_u16::<BigEndian>()?;
+    let channel_count = file.read_u16::<BigEndian>()?;
+    let displayed_channel_count = file.read_u16::<BigEndian>()?;
+
+    log::trace!(
+        "Score: {} frames, {} channels of {} bytes",
+        frame_count, channel_count, sprite_record_size
+    );
+
+    if sprite_record_size as usize != SPRITE_RECORD_SIZE {
+        return Err(DirpError::UnsupportedScore(sprite_record_size));
+    }
+
+    let sprite_count = (channel_count as usize * SPRITE_RECORD_SIZE)
+        .saturating_sub(MAIN_CHANNELS_SIZE) / SPRITE_RECORD_SIZE;
+
+    // The channels of the frame before, which the frames change
+    let mut channels = vec![0; MAIN_CHANNELS_SIZE + sprite_count * SPRITE_RECORD_SIZE];
+
+    let frames_end = frames_offset + frames_length;
+
+    file.seek(SeekFrom::Start(frames_offset + header_length))?;
+
+    let mut frames = Vec::new();
+
+    while frames.len() < frame_count as usize && file.position() < frames_end {
+        let frame_length = file.read_u16::<BigEndian>()? as u64;
+
+        if frame_length == 0 {
+            break;
+        }
+
+        // The length includes itself
+        let frame_end = file.position() + frame_length.saturating_sub(2);
+
+        while file.position() < frame_end {
+            let len = file.read_u16::<BigEndian>()? as usize;
+            let offset = file.read_u16::<BigEndian>()? as usize;
+
+            // Changes to channels past the ones in the header are
+            // kept, so that the offsets of the others still match.
+            if channels.len() < offset + len {
+                channels.resize(offset + len, 0);
+            }
+
+            file.read_exact(&mut channels[offset..offset + len])?;
+        }
+
+        frames.push(read_frame(&channels, sprite_count)?);
+    }
+
+    Ok(Score {
+        frames_version,
+        sprite_record_size,
+        channel_count,
+        displayed_channel_count,
+        frames,
+    })
+}
+
+fn read_member_ref<R: Read + Endian>(file: &mut R) -> Result<MemberRef, DirpError> {
+    Ok(MemberRef {
+        cast: file.read_i16::<BigEndian>()?,
+        member: file.read_i16::<BigEndian>()?,
+    })
+}
+
+fn read_frame(channels: &[u8], sprite_count: usize) -> Result<Frame, DirpError> {
+    let mut file = Cursor::new(channels);
+
+    let script = read_member_ref(&mut file)?;
+    let sound1 = read_member_ref(&mut file)?;
+    let sound2 = read_member_ref(&mut file)?;
+    let transition = read_member_ref(&mut file)?;
+
+    file.seek(SeekFrom::Start(21))?;
+    let tempo = file.read_u8()?;
+
+    file.seek(SeekFrom::Start(24))?;
+    let palette = read_member_ref(&mut file)?;
+    let palette_speed = file.read_u8()?;
+    let palette_flags = file.read_u8()?;
+
+    file.seek(SeekFrom::Start(MAIN_CHANNELS_SIZE as u64))?;
+
+    let mut sprites = Vec::with_capacity(sprite_count);
+
+    for _ in 0..sprite_count {
+        sprites.push(read_sprite(&mut file)?);
+    }
+
+    Ok(Frame {
+        script,
+        tempo,
+        transition,
+        sound1,
+        sound2,
+        palette,
+        palette_speed,
+        palette_flags,
+        sprites,
+    })
+}
+
+fn read_sprite<R: Read + Endian>(file: &mut R) -> Result<Sprite, DirpError> {
+    let kind = file.read_u8()?;
+    let ink = file.read_u8()?;
+    let member = read_member_ref(file)?;
+    let script = read_member_ref(file)?;
+    let fore_color = file.read_u8()?;
+    let back_color = file.read_u8()?;
+    let top = file.read_i16::<BigEndian>()?;
+    let left = file.read_i16::<BigEndian>()?;
+    let height = file.read_i16::<BigEndian>()?;
+    let width = file.read_i16::<BigEndian>()?;
+    let color_code = file.read_u8()?;
+    let blend = file.read_u8()?;
+    let line_size = file.read_u8()?;
+    let _flags = file.read_u8()?;
+
+    Ok(Sprite {
+        kind,
+        ink,
+        member,
+        script,
+        fore_color,
+        back_color,
+        top,
+        left,
+        height,
+        width,
+        color_code,
+        blend,
+        line_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn delta(offset: u16, data: &[u8]) -> Vec<u8> {
+        let mut delta = Vec::new();
+        delta.extend_from_slice(&(data.len() as u16).to_be_bytes());
+        delta.extend_from_slice(&offset.to_be_bytes());
+        delta.extend_from_slice(data);
+        delta
+    }
+
+    fn frame(deltas: &[Vec<u8>]) -> Vec<u8> {
+        let deltas = deltas.concat();
+
+        let mut frame = ((deltas.len() + 2) as u16).to_be_bytes().to_vec();
+        frame.extend(deltas);
+        frame
+    }
+
+    // Two frames of four channels, which are the special channels
+    // and two sprite channels.
+    fn frames() -> Vec<u8> {
+        let sprite = [
+            1, 0x48, 0, 1, 0, 3, 0, 1, 0, 9, 255, 0,
+            0, 10, 0, 20, 0, 30, 0, 40, 0, 128, 1, 0,
+        ];
+
+        let frames = [
+            frame(&[delta(0, &[0, 1, 0, 5]), delta(21, &[30]), delta(48, &sprite)]),
+            // Moves the sprite and puts something in a channel past the header
+            frame(&[delta(48 + 14, &[0, 99]), delta(96 + 24, &[1])]),
+        ].concat();
+
+        let mut data = Vec::new();
+        data.extend_from_slice(&(20 + frames.len() as u32).to_be_bytes());
+        data.extend_from_slice(&20u32.to_be_bytes());
+        data.extend_from_slice(&2u32.to_be_bytes());
+        data.extend_from_slice(&[0, 13, 0, 24, 0, 4, 0, 2]);
+        data.extend(frames);
+        data
+    }
+
+    fn read(body: &[u8]) -> Score {
+        let mut chunk = b"VWSC".to_vec();
+        chunk.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        chunk.extend_from_slice(body);
+
+        read_vwsc::<_, BigEndian>(&mut Cursor::new(chunk)).unwrap()
+    }
+
+    fn check(score: &Score) {
+        assert_eq!(score.frames().len(), 2);
+        assert_eq!(score.channel_count(), 4);
+
+        for frame in score.frames() {
+            assert_eq!(frame.script(), MemberRef { cast: 1, member: 5 });
+            assert_eq!(frame.tempo(), 30);
+            assert_eq!(frame.sprites().len(), 2);
+            assert!(frame.sprite(2).is_none());
+        }
+
+        let first = score.frame(1).unwrap().sprite(1).unwrap();
+        let second = score.frame(2).unwrap().sprite(1).unwrap();
+
+        assert_eq!(first.position(), (20, 10));
+        assert_eq!(second.position(), (99, 10));
+
+        // Everything the second frame doesn't change is kept
+        for sprite in [first, second] {
+            assert_eq!(sprite.kind(), 1);
+            assert_eq!(sprite.ink(), 8);
+            assert!(sprite.trails());
+            assert_eq!(sprite.member(), MemberRef { cast: 1, member: 3 });
+            assert_eq!(sprite.script(), MemberRef { cast: 1, member: 9 });
+            assert_eq!(sprite.fore_color(), 255);
+            assert_eq!((sprite.width(), sprite.height()), (40, 30));
+            assert_eq!(sprite.blend(), 128);
+            assert_eq!(sprite.line_size(), 1);
+        }
+    }
+
+    #[test]
+    fn delta_frames() {
+        check(&read(&frames()));
+    }
+
+    #[test]
+    fn entries() {
+        let frames = frames();
+        let other = [0xaa, 0xbb];
+
+        // The frames are the first of two entries
+        let mut body = Vec::new();
+        for value in [0, -3, 12, 2, 3, (frames.len() + other.len()) as i32] {
+            body.extend_from_slice(&value.to_be_bytes());
+        }
+        for offset in [0, frames.len(), frames.len() + other.len()] {
+            body.extend_from_slice(&(offset as u32).to_be_bytes());
+        }
+        body.extend(frames);
+        body.extend_from_slice(&other);
+
+        check(&read(&body));
+    }
+
+    #[test]
+    fn unsupported_sprite_records() {
+        let mut body = frames();
+        body[15] = 48;
+
+        let mut chunk = b"VWSC".to_vec();
+        chunk.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        chunk.extend(body);
+
+        assert!(matches!(
+            read_vwsc::<_, BigEndian>(&mut Cursor::new(chunk)),
+            Err(DirpError::UnsupportedScore(48))
+        ));
+    }
+}
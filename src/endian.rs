@@ -1,5 +1,7 @@
 use std::io::Read;
 
+use crate::error::DirpError;
+
 pub struct BigEndian;
 pub struct LittleEndian;
 
@@ -19,54 +21,238 @@ impl Endianness for LittleEndian {
     }
 }
 
+// All reads fail with DirpError::Truncated if the reader
+// runs out of bytes before the value is complete.
 pub trait Endian: Read {
-    fn read_bytes<E: Endianness>(&mut self, bytes: &mut [u8]) {
-        self.read(bytes).unwrap();
+    fn read_bytes<E: Endianness>(&mut self, bytes: &mut [u8]) -> Result<(), DirpError> {
+        self.read_exact(bytes)?;
 
         if !E::is_big_endian() {
             bytes.reverse();
         }
+
+        Ok(())
     }
 
-    fn read_u8(&mut self) -> u8 {
+    fn read_u8(&mut self) -> Result<u8, DirpError> {
         let mut bytes = [0; 1];
-        self.read(&mut bytes).unwrap();
+        self.read_exact(&mut bytes)?;
         // a byte is the same in BE and LE
-        u8::from_be_bytes(bytes)
+        Ok(u8::from_be_bytes(bytes))
     }
 
-    fn read_u16<E: Endianness>(&mut self) -> u16 {
+    fn read_u16<E: Endianness>(&mut self) -> Result<u16, DirpError> {
         let mut bytes = [0; 2];
-        self.read(&mut bytes).unwrap();
+        self.read_exact(&mut bytes)?;
 
         if E::is_big_endian() {
-            u16::from_be_bytes(bytes)
+            Ok(u16::from_be_bytes(bytes))
         } else {
-            u16::from_le_bytes(bytes)
+            Ok(u16::from_le_bytes(bytes))
         }
     }
 
-    fn read_u32<E: Endianness>(&mut self) -> u32 {
+    fn read_u32<E: Endianness>(&mut self) -> Result<u32, DirpError> {
         let mut bytes = [0; 4];
-        self.read(&mut bytes).unwrap();
+        self.read_exact(&mut bytes)?;
 
         if E::is_big_endian() {
-            u32::from_be_bytes(bytes)
+            Ok(u32::from_be_bytes(bytes))
         } else {
-            u32::from_le_bytes(bytes)
+            Ok(u32::from_le_bytes(bytes))
         }
     }
 
-    fn read_u64<E: Endianness>(&mut self) -> u64 {
+    fn read_u64<E: Endianness>(&mut self) -> Result<u64, DirpError> {
         let mut bytes = [0; 8];
-        self.read(&mut bytes).unwrap();
+        self.read_exact(&mut bytes)?;
 
         if E::is_big_endian() {
-            u64::from_be_bytes(bytes)
+            Ok(u64::from_be_bytes(bytes))
         } else {
-            u64::from_le_bytes(bytes)
+            Ok(u64::from_le_bytes(bytes))
+        }
+    }
+
+    fn read_i8(&mut self) -> Result<i8, DirpError> {
+        Ok(self.read_u8()? as i8)
+    }
+
+    fn read_i16<E: Endianness>(&mut self) -> Result<i16, DirpError> {
+        Ok(self.read_u16::<E>()? as i16)
+    }
+
+    fn read_i32<E: Endianness>(&mut self) -> Result<i32, DirpError> {
+        Ok(self.read_u32::<E>()? as i32)
+    }
+
+    // A signed 16.16 fixed-point number.
+    fn read_fixed<E: Endianness>(&mut self) -> Result<f64, DirpError> {
+        Ok(self.read_i32::<E>()? as f64 / 65536.0)
+    }
+
+    // A signed 8.8 fixed-point number.
+    fn read_short_fixed<E: Endianness>(&mut self) -> Result<f32, DirpError> {
+        Ok(self.read_i16::<E>()? as f32 / 256.0)
+    }
+
+    fn read_f64<E: Endianness>(&mut self) -> Result<f64, DirpError> {
+        Ok(f64::from_bits(self.read_u64::<E>()?))
+    }
+
+    // An 80-bit extended precision float, as used on 68k Macs. It has a
+    // sign bit, a 15-bit exponent and a 64-bit mantissa with an explicit
+    // integer bit.
+    fn read_extended<E: Endianness>(&mut self) -> Result<f64, DirpError> {
+        let exponent = self.read_u16::<E>()?;
+        let mantissa = self.read_u64::<E>()?;
+
+        let sign = if exponent & 0x8000 != 0 { -1.0 } else { 1.0 };
+        let exponent = exponent & 0x7fff;
+
+        if exponent == 0 && mantissa == 0 {
+            return Ok(sign * 0.0);
+        }
+
+        if exponent == 0x7fff {
+            if mantissa << 1 == 0 {
+                return Ok(sign * f64::INFINITY);
+            } else {
+                return Ok(f64::NAN);
+            }
+        }
+
+        let value = mantissa as f64 * 2f64.powi(exponent as i32 - 16383 - 63);
+
+        Ok(sign * value)
+    }
+
+    // A variable-length integer, as used by Afterburner. Each byte holds
+    // seven bits of the value, most significant first, and the high bit
+    // is set on every byte except the last.
+    fn read_varint(&mut self) -> Result<u32, DirpError> {
+        let mut value = 0u32;
+
+        loop {
+            let b = self.read_u8()?;
+            value = (value << 7) | (b & 0x7f) as u32;
+
+            if b & 0x80 == 0 {
+                return Ok(value);
+            }
         }
     }
+
+    // A string of exactly 'len' bytes. Strings in movies are often Mac Roman
+    // rather than UTF-8, so invalid bytes are replaced, as in every string
+    // we read.
+    fn read_string(&mut self, len: usize) -> Result<String, DirpError> {
+        let mut bytes = vec![0; len];
+        self.read_exact(&mut bytes)?;
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    // A string prefixed with its length as a single byte.
+    fn read_pascal_string(&mut self) -> Result<String, DirpError> {
+        let len = self.read_u8()? as usize;
+
+        self.read_string(len)
+    }
+
+    // A string terminated by a NULL byte, which is consumed
+    // but not included in the string.
+    fn read_c_string(&mut self) -> Result<String, DirpError> {
+        let mut bytes = Vec::new();
+
+        loop {
+            match self.read_u8()? {
+                0 => break,
+                b => bytes.push(b),
+            }
+        }
+
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
 }
 
 impl<R: Read> Endian for R {}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn integers_in_both_endiannesses() {
+        let bytes = [0x12, 0x34, 0x56, 0x78];
+
+        assert_eq!(Cursor::new(&bytes).read_u16::<BigEndian>().unwrap(), 0x1234);
+        assert_eq!(Cursor::new(&bytes).read_u16::<LittleEndian>().unwrap(), 0x3412);
+        assert_eq!(Cursor::new(&bytes).read_u32::<BigEndian>().unwrap(), 0x12345678);
+        assert_eq!(Cursor::new(&bytes).read_u32::<LittleEndian>().unwrap(), 0x78563412);
+
+        let bytes = [0xff, 0xfe];
+
+        assert_eq!(Cursor::new(&bytes).read_i16::<BigEndian>().unwrap(), -2);
+        assert_eq!(Cursor::new(&bytes).read_i16::<LittleEndian>().unwrap(), -257);
+    }
+
+    #[test]
+    fn fixed_point() {
+        let bytes = [0xff, 0xfe, 0x80, 0x00];
+        assert_eq!(Cursor::new(&bytes).read_fixed::<BigEndian>().unwrap(), -1.5);
+
+        let bytes = [0x00, 0x80, 0x01, 0x00];
+        assert_eq!(Cursor::new(&bytes).read_fixed::<LittleEndian>().unwrap(), 1.5);
+
+        let bytes = [0x01, 0x40];
+        assert_eq!(Cursor::new(&bytes).read_short_fixed::<BigEndian>().unwrap(), 1.25);
+
+        let bytes = [0x80, 0xfe];
+        assert_eq!(Cursor::new(&bytes).read_short_fixed::<LittleEndian>().unwrap(), -1.5);
+    }
+
+    #[test]
+    fn extended() {
+        let read = |bytes: [u8; 10]| Cursor::new(&bytes).read_extended::<BigEndian>().unwrap();
+
+        assert_eq!(read([0; 10]), 0.0);
+        assert_eq!(read([0x3f, 0xff, 0x80, 0, 0, 0, 0, 0, 0, 0]), 1.0);
+        assert_eq!(read([0xc0, 0x00, 0x80, 0, 0, 0, 0, 0, 0, 0]), -2.0);
+        assert_eq!(read([0x40, 0x00, 0xc0, 0, 0, 0, 0, 0, 0, 0]), 3.0);
+        assert_eq!(read([0x7f, 0xff, 0x80, 0, 0, 0, 0, 0, 0, 0]), f64::INFINITY);
+        assert!(read([0x7f, 0xff, 0xc0, 0, 0, 0, 0, 0, 0, 0]).is_nan());
+
+        let bytes = [0xff, 0x3f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80];
+        assert_eq!(Cursor::new(&bytes).read_extended::<LittleEndian>().unwrap(), 1.0);
+    }
+
+    #[test]
+    fn varint() {
+        assert_eq!(Cursor::new([0x05]).read_varint().unwrap(), 5);
+        assert_eq!(Cursor::new([0x81, 0x00]).read_varint().unwrap(), 128);
+        assert_eq!(Cursor::new([0x82, 0x80, 0x01, 0xff]).read_varint().unwrap(), 0x8001);
+    }
+
+    #[test]
+    fn strings() {
+        assert_eq!(Cursor::new(b"abcd").read_string(3).unwrap(), "abc");
+        assert_eq!(Cursor::new(b"\x02hiX").read_pascal_string().unwrap(), "hi");
+        assert_eq!(Cursor::new(b"hello\0world").read_c_string().unwrap(), "hello");
+
+        // Mac Roman "é", which isn't valid UTF-8
+        assert_eq!(Cursor::new(b"caf\x8e").read_string(4).unwrap(), "caf\u{fffd}");
+        assert_eq!(Cursor::new(b"\x8e\0").read_c_string().unwrap(), "\u{fffd}");
+    }
+
+    #[test]
+    fn truncated() {
+        assert!(matches!(Cursor::new([0x12]).read_u16::<BigEndian>(), Err(DirpError::Truncated)));
+        assert!(matches!(Cursor::new([0x3f, 0xff, 0x80]).read_extended::<BigEndian>(), Err(DirpError::Truncated)));
+        assert!(matches!(Cursor::new([0x81, 0x80]).read_varint(), Err(DirpError::Truncated)));
+        assert!(matches!(Cursor::new(b"\x05hi").read_pascal_string(), Err(DirpError::Truncated)));
+        assert!(matches!(Cursor::new(b"no null").read_c_string(), Err(DirpError::Truncated)));
+    }
+}
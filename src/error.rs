@@ -0,0 +1,129 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::chunk::cast::CastProperty;
+
+#[derive(Debug)]
+pub enum DirpError {
+    // An error from the underlying reader.
+    Io(io::Error),
+    // The file ended in the middle of a value.
+    Truncated,
+    // The file did not start with RIFX or XFIR.
+    InvalidHeader([u8; 4]),
+    // A chunk did not start with the FourCC we expected.
+    InvalidChunk {
+        expected: [u8; 4],
+        found: [u8; 4],
+        offset: u64,
+    },
+    // The cast type of a CASt chunk is not one we know of.
+    UnknownCastKind(u32),
+    // The key table has no 'chunk' owned by 'owner'.
+    MissingKey {
+        owner: u32,
+        chunk: [u8; 4],
+    },
+    // The resource ID is not in the memory map.
+    MissingChunk(u32),
+    // The memory map has no KEY* chunk.
+    MissingKeyTable,
+    // The Afterburner map has a resource ID too large to be real.
+    InvalidResourceId(u32),
+    // A cast member is missing a property needed to decode it.
+    MissingProperty(CastProperty),
+    // Compressed Afterburner data could not be decompressed.
+    Decompression(io::Error),
+    // The compressed data of a bitmap decompressed to more bytes
+    // than the bitmap can hold.
+    BitmapOverrun {
+        expected: usize,
+        found: usize,
+    },
+    // The cast member with this number has no decoded bitmap to export.
+    NotABitmap(u32),
+    // The bitmap of the cast member with this number is empty, or its
+    // pixels don't match its size, so it can't be exported.
+    UnexportableBitmap(u32),
+    // The score has sprite records of a size we don't know the layout of.
+    UnsupportedScore(u16),
+}
+
+impl fmt::Display for DirpError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DirpError::Io(e) => write!(f, "I/O error: {}", e),
+            DirpError::Truncated => write!(f, "unexpected end of file"),
+            DirpError::InvalidHeader(found) => {
+                write!(f, "expected RIFX or XFIR, found {}", fourcc(found))
+            },
+            DirpError::InvalidChunk { expected, found, offset } => {
+                write!(
+                    f, "expected chunk {} at offset {:#x}, found {}",
+                    fourcc(expected), offset, fourcc(found)
+                )
+            },
+            DirpError::UnknownCastKind(kind) => {
+                write!(f, "unknown cast type: {}", kind)
+            },
+            DirpError::MissingKey { owner, chunk } => {
+                write!(f, "no {} chunk owned by {}", fourcc(chunk), owner)
+            },
+            DirpError::MissingChunk(id) => {
+                write!(f, "resource {} is not in the memory map", id)
+            },
+            DirpError::MissingKeyTable => {
+                write!(f, "no KEY* chunk in the memory map")
+            },
+            DirpError::InvalidResourceId(id) => {
+                write!(f, "invalid resource ID {} in the Afterburner map", id)
+            },
+            DirpError::MissingProperty(property) => {
+                write!(f, "cast member is missing property {:?}", property)
+            },
+            DirpError::Decompression(e) => {
+                write!(f, "could not decompress data: {}", e)
+            },
+            DirpError::BitmapOverrun { expected, found } => {
+                write!(
+                    f, "bitmap data decompressed to {} bytes, expected {}",
+                    found, expected
+                )
+            },
+            DirpError::NotABitmap(member) => {
+                write!(f, "member {} has no decoded bitmap", member)
+            },
+            DirpError::UnexportableBitmap(member) => {
+                write!(f, "the bitmap of member {} can't be exported", member)
+            },
+            DirpError::UnsupportedScore(size) => {
+                write!(f, "unsupported score with {} byte sprite records", size)
+            },
+        }
+    }
+}
+
+impl Error for DirpError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DirpError::Io(e) => Some(e),
+            DirpError::Decompression(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for DirpError {
+    fn from(e: io::Error) -> DirpError {
+        match e.kind() {
+            io::ErrorKind::UnexpectedEof => DirpError::Truncated,
+            _ => DirpError::Io(e),
+        }
+    }
+}
+
+// Format a FourCC for error messages, even if it isn't valid text.
+fn fourcc(chunk: &[u8; 4]) -> String {
+    String::from_utf8_lossy(chunk).into_owned()
+}
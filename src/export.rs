@@ -0,0 +1,265 @@
+use std::io::Write;
+
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+
+use crate::DirpError;
+use crate::{Bitmap, CastMember, MemberData};
+
+// Write the bitmap of 'member' as a PNG image with an alpha channel.
+// The registration point is stored in a tEXt chunk with the keyword
+// "Registration Point", as "x,y" relative to the top left of the image.
+pub fn write_png<W: Write>(member: &CastMember, writer: &mut W) -> Result<(), DirpError> {
+    let bitmap = bitmap(member)?;
+
+    let height = bitmap.height();
+    let width = bitmap.width();
+
+    // PNG images must have at least one pixel
+    if width == 0 || height == 0 {
+        return Err(DirpError::UnexportableBitmap(member.number()));
+    }
+
+    writer.write_all(b"\x89PNG\r\n\x1a\n")?;
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&(width as u32).to_be_bytes());
+    header.extend_from_slice(&(height as u32).to_be_bytes());
+    // 8 bits per channel, RGBA, deflate, adaptive filtering, no interlace
+    header.extend_from_slice(&[8, 6, 0, 0, 0]);
+
+    write_png_chunk(writer, b"IHDR", &header)?;
+
+    if let Some((x, y)) = registration_point(member) {
+        let text = format!("Registration Point\0{},{}", x, y);
+
+        write_png_chunk(writer, b"tEXt", text.as_bytes())?;
+    }
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+
+    let stride = bitmap.stride();
+
+    for y in 0..height {
+        // Every row starts with its filter type, which is none
+        encoder.write_all(&[0])?;
+        encoder.write_all(&bitmap.pixels()[y * stride..(y + 1) * stride])?;
+    }
+
+    write_png_chunk(writer, b"IDAT", &encoder.finish()?)?;
+    write_png_chunk(writer, b"IEND", &[])?;
+
+    Ok(())
+}
+
+// Write the bitmap of 'member' as a 32-bit BMP image with an alpha channel.
+// NOTE BMP has nowhere to store the registration point, so it is lost.
+pub fn write_bmp<W: Write>(member: &CastMember, writer: &mut W) -> Result<(), DirpError> {
+    let bitmap = bitmap(member)?;
+
+    let height = bitmap.height() as u32;
+    let width = bitmap.width() as u32;
+
+    // Like PNG, an empty BMP image isn't accepted by most readers
+    if width == 0 || height == 0 {
+        return Err(DirpError::UnexportableBitmap(member.number()));
+    }
+
+    // The file header, followed by a BITMAPV4HEADER
+    let offset = 14 + 108;
+    let size = width * height * 4;
+
+    writer.write_all(b"BM")?;
+    writer.write_all(&(offset + size).to_le_bytes())?;
+    writer.write_all(&[0; 4])?;
+    writer.write_all(&offset.to_le_bytes())?;
+
+    writer.write_all(&108u32.to_le_bytes())?;
+    writer.write_all(&(width as i32).to_le_bytes())?;
+    // A negative height puts the top row first
+    writer.write_all(&(-(height as i32)).to_le_bytes())?;
+    // One plane of 32 bits per pixel
+    writer.write_all(&1u16.to_le_bytes())?;
+    writer.write_all(&32u16.to_le_bytes())?;
+    // BI_BITFIELDS
+    writer.write_all(&3u32.to_le_bytes())?;
+    writer.write_all(&size.to_le_bytes())?;
+    // 72 DPI
+    writer.write_all(&2835u32.to_le_bytes())?;
+    writer.write_all(&2835u32.to_le_bytes())?;
+    // No color table
+    writer.write_all(&[0; 8])?;
+    // The red, green, blue and alpha masks, for pixels stored as BGRA
+    writer.write_all(&0x00ff_0000u32.to_le_bytes())?;
+    writer.write_all(&0x0000_ff00u32.to_le_bytes())?;
+    writer.write_all(&0x0000_00ffu32.to_le_bytes())?;
+    writer.write_all(&0xff00_0000u32.to_le_bytes())?;
+    // LCS_sRGB, with no endpoints or gamma
+    writer.write_all(b"BGRs")?;
+    writer.write_all(&[0; 48])?;
+
+    for pixel in bitmap.pixels().chunks(4) {
+        writer.write_all(&[pixel[2], pixel[1], pixel[0], pixel[3]])?;
+    }
+
+    Ok(())
+}
+
+// Convert the bitmap of 'member' to an image of the image crate.
+#[cfg(feature = "image")]
+pub fn to_image(member: &CastMember) -> Result<image::RgbaImage, DirpError> {
+    let bitmap = bitmap(member)?;
+
+    let width = bitmap.width() as u32;
+    let height = bitmap.height() as u32;
+
+    image::RgbaImage::from_raw(width, height, bitmap.pixels().clone())
+        .ok_or(DirpError::UnexportableBitmap(member.number()))
+}
+
+fn bitmap(member: &CastMember) -> Result<&Bitmap, DirpError> {
+    match member.data() {
+        MemberData::Bitmap(bitmap) => Ok(bitmap),
+        _ => Err(DirpError::NotABitmap(member.number())),
+    }
+}
+
+// The registration point of 'member', relative to the top left of the bitmap.
+fn registration_point(member: &CastMember) -> Option<(i32, i32)> {
+    let info = member.bitmap_info()?;
+    let rect = info.rect();
+    let (x, y) = info.registration_point();
+
+    Some((x as i32 - rect.left as i32, y as i32 - rect.top as i32))
+}
+
+fn write_png_chunk<W: Write>(writer: &mut W, chunk: &[u8; 4], data: &[u8]) -> Result<(), DirpError> {
+    writer.write_all(&(data.len() as u32).to_be_bytes())?;
+    writer.write_all(chunk)?;
+    writer.write_all(data)?;
+
+    let crc = crc32(crc32(0xffff_ffff, chunk), data) ^ 0xffff_ffff;
+    writer.write_all(&crc.to_be_bytes())?;
+
+    Ok(())
+}
+
+// Update the CRC-32 'crc' with 'data', as used by PNG chunks.
+fn crc32(crc: u32, data: &[u8]) -> u32 {
+    data.iter().fold(crc, |crc, &byte| {
+        (0..8).fold(crc ^ byte as u32, |crc, _| {
+            if crc & 1 == 1 {
+                0xedb8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            }
+        })
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryInto;
+    use std::io::Read;
+
+    use flate2::read::ZlibDecoder;
+
+    use super::*;
+
+    use crate::chunk::cast::BitmapInfo;
+
+    // A 2x2 bitmap of red, green, blue and transparent white.
+    const PIXELS: [u8; 16] = [
+        255, 0, 0, 255, 0, 255, 0, 255,
+        0, 0, 255, 255, 255, 255, 255, 0,
+    ];
+
+    fn member(width: usize, height: usize, pixels: &[u8]) -> CastMember {
+        let info = BitmapInfo::new(width as i16, height as i16, 32, width * 4);
+
+        CastMember::bitmap(7, info, Bitmap::new(width, height, pixels.to_vec()))
+    }
+
+    fn u32_at(data: &[u8], offset: usize) -> u32 {
+        u32::from_be_bytes(data[offset..offset + 4].try_into().unwrap())
+    }
+
+    // Split a PNG image into its chunks, checking their CRCs.
+    fn png_chunks(png: &[u8]) -> Vec<([u8; 4], Vec<u8>)> {
+        let mut chunks = Vec::new();
+        let mut offset = 8;
+
+        while offset < png.len() {
+            let len = u32_at(png, offset) as usize;
+            let chunk: [u8; 4] = png[offset + 4..offset + 8].try_into().unwrap();
+            let data = png[offset + 8..offset + 8 + len].to_vec();
+
+            let crc = crc32(crc32(0xffff_ffff, &chunk), &data) ^ 0xffff_ffff;
+            assert_eq!(u32_at(png, offset + 8 + len), crc);
+
+            chunks.push((chunk, data));
+            offset += 12 + len;
+        }
+
+        chunks
+    }
+
+    #[test]
+    fn crc() {
+        // The check value of CRC-32
+        assert_eq!(crc32(0xffff_ffff, b"123456789") ^ 0xffff_ffff, 0xcbf4_3926);
+    }
+
+    #[test]
+    fn png() {
+        let mut png = Vec::new();
+        write_png(&member(2, 2, &PIXELS), &mut png).unwrap();
+
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        let chunks = png_chunks(&png);
+        let names: Vec<&[u8; 4]> = chunks.iter().map(|(chunk, _)| chunk).collect();
+        assert_eq!(names, [b"IHDR", b"tEXt", b"IDAT", b"IEND"]);
+
+        assert_eq!(chunks[0].1, [0, 0, 0, 2, 0, 0, 0, 2, 8, 6, 0, 0, 0]);
+        assert_eq!(chunks[1].1, b"Registration Point\x000,0");
+
+        let mut rows = Vec::new();
+        ZlibDecoder::new(&chunks[2].1[..]).read_to_end(&mut rows).unwrap();
+
+        // Every row starts with a filter type of none
+        assert_eq!(rows[0], 0);
+        assert_eq!(rows[1..9], PIXELS[..8]);
+        assert_eq!(rows[9], 0);
+        assert_eq!(rows[10..], PIXELS[8..]);
+    }
+
+    #[test]
+    fn bmp() {
+        let mut bmp = Vec::new();
+        write_bmp(&member(2, 2, &PIXELS), &mut bmp).unwrap();
+
+        let le = |offset: usize| u32::from_le_bytes(bmp[offset..offset + 4].try_into().unwrap());
+
+        assert_eq!(&bmp[..2], b"BM");
+        assert_eq!(le(2) as usize, bmp.len());
+        assert_eq!(le(10), 14 + 108);
+        assert_eq!(le(14), 108);
+        assert_eq!(le(18), 2);
+        // A negative height, for rows from the top down
+        assert_eq!(le(22) as i32, -2);
+        assert_eq!(le(30), 3);
+
+        // The pixels are stored as BGRA
+        assert_eq!(bmp[122..126], [0, 0, 255, 255]);
+        assert_eq!(bmp[134..138], [255, 255, 255, 0]);
+    }
+
+    #[test]
+    fn empty_bitmap() {
+        let member = member(0, 0, &[]);
+
+        assert!(matches!(write_png(&member, &mut Vec::new()), Err(DirpError::UnexportableBitmap(7))));
+        assert!(matches!(write_bmp(&member, &mut Vec::new()), Err(DirpError::UnexportableBitmap(7))));
+    }
+}
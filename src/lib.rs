@@ -1,13 +1,41 @@
 use std::fs::File;
-use std::io::{self, Read, Seek, SeekFrom};
+use std::io::{Cursor, Read, Seek, SeekFrom};
 use std::path::Path;
-use std::collections::VecDeque;
+use std::collections::HashMap;
+use std::convert::TryFrom;
 
-mod chunk;
+pub mod chunk;
 
-mod endian;
+pub mod endian;
 
-use chunk::Chunk;
+mod error;
+
+pub use error::DirpError;
+
+mod cast_library;
+
+mod bitmap;
+
+pub use bitmap::Bitmap;
+
+pub mod palette;
+
+pub mod export;
+
+pub use cast_library::{CastLibrary, CastMember, MemberData};
+
+mod warning;
+
+pub use warning::Warning;
+
+mod resolver;
+
+pub mod lingo;
+
+#[cfg(test)]
+mod test_movie;
+
+pub use resolver::{CastLoader, CastResolver};
 
 use chunk::rifx::Header;
 use chunk::rifx::Endianness;
@@ -17,6 +45,7 @@ use chunk::imap::InitialMap;
 
 use chunk::mmap;
 use chunk::mmap::MemoryMap;
+use chunk::mmap::MemoryMapEntry;
 
 use chunk::key;
 use chunk::key::KeyTable;
@@ -24,57 +53,138 @@ use chunk::key::KeyTable;
 use chunk::mcsl;
 use chunk::mcsl::MovieCastList;
 
-use chunk::cas;
-use chunk::cas::CastTable;
+use chunk::lscr;
+use chunk::lscr::Script;
+
+use chunk::lctx;
+use chunk::lctx::ScriptContext;
+
+use chunk::lnam;
+use chunk::lnam::NameTable;
+
+use chunk::drcf;
+use chunk::drcf::Config;
+
+use chunk::vwsc;
+use chunk::vwsc::Score;
 
-use chunk::cast;
 use chunk::cast::CastProperties;
 use chunk::cast::CastProperty;
-use chunk::cast::CastKind;
 
-use chunk::bitd;
-use chunk::bitd::BitmapData;
+use chunk::fver;
+
+use chunk::fcdr;
+use chunk::fcdr::CompressionId;
+
+use chunk::abmp;
+
+use chunk::fgei;
 
 use endian::{BigEndian, LittleEndian};
 
 pub struct DirectorFile {
     header: Header,
-    imap: InitialMap,
+    imap: Option<InitialMap>,
     mmap: MemoryMap,
     key: KeyTable,
+    chunks: Chunks,
+    casts: Vec<CastLibrary>,
+    warnings: Vec<Warning>,
+}
+
+// Where the data of the chunks is read from.
+enum Chunks {
+    // The chunks of an uncompressed movie are read from
+    // the offsets in the memory map.
+    File(Vec<u8>),
+    // The chunks of an Afterburner movie are decompressed up front,
+    // with a chunk header added so that they can be read just like
+    // the chunks of an uncompressed movie.
+    Decompressed(HashMap<u32, Vec<u8>>),
 }
 
 impl DirectorFile {
-    // Read the chunks RIFX -> imap -> mmap -> KEY*
-    pub fn base<P: AsRef<Path>>(file: P) -> io::Result<DirectorFile> {
-        let mut file = File::open(file.as_ref())?;
+    // Read the chunks RIFX -> imap -> mmap -> KEY*, or for Afterburner
+    // movies RIFX -> Fver -> Fcdr -> ABMP -> FGEI -> KEY*
+    pub fn base<P: AsRef<Path>>(file: P) -> Result<DirectorFile, DirpError> {
+        DirectorFile::base_from_reader(File::open(file.as_ref())?)
+    }
 
-        let header = chunk::rifx::read_rifx(&mut file);
+    // Read the base chunks of a movie from 'reader', starting at its current
+    // position. Everything from there to the end of the reader is buffered
+    // in memory, as chunks are read from it by offset, so 'reader' doesn't
+    // need to be seekable.
+    pub fn base_from_reader<R: Read>(mut reader: R) -> Result<DirectorFile, DirpError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
 
-        let (imap, mmap, key) = match header.endian() {
-            Endianness::Big => read_base_chunks::<File, BigEndian>(&mut file),
-            Endianness::Little => read_base_chunks::<File, LittleEndian>(&mut file),
+        let mut file = Cursor::new(data);
+
+        let header = chunk::rifx::read_rifx(&mut file)?;
+
+        let mut warnings = Vec::new();
+
+        let (imap, mmap, chunks) = match header.endian() {
+            Endianness::Big => read_base_chunks::<BigEndian>(&header, file, &mut warnings)?,
+            Endianness::Little => read_base_chunks::<LittleEndian>(&header, file, &mut warnings)?,
+        };
+
+        // The key table is the first KEY* chunk in the memory map.
+        let key_id = mmap.entries().iter()
+            .position(|entry| entry.chunk() == "KEY*")
+            .ok_or(DirpError::MissingKeyTable)? as u32;
+
+        let mut file = chunks.reader(&mmap, key_id)?;
+
+        let key = match header.endian() {
+            Endianness::Big => key::read_key::<_, BigEndian>(&mut file)?,
+            Endianness::Little => key::read_key::<_, LittleEndian>(&mut file)?,
         };
 
         let df = DirectorFile {
-            header: header,
-            imap: imap,
-            mmap: mmap,
-            key: key,
+            header,
+            imap,
+            mmap,
+            key,
+            chunks,
+            casts: Vec::new(),
+            warnings,
         };
 
         Ok(df)
     }
 
-    // Read a dir/dxr file
-    pub fn new<P: AsRef<Path>>(file: P) -> io::Result<DirectorFile> {
-        let mut base = DirectorFile::base(file.as_ref())?;
+    // Read a dir/dxr file, or an Afterburner compressed dcr/cct file
+    pub fn new<P: AsRef<Path>>(file: P) -> Result<DirectorFile, DirpError> {
+        DirectorFile::with_resolver(file, &CastResolver::new())
+    }
+
+    // Read a movie, using 'resolver' to find its external casts.
+    pub fn with_resolver<P: AsRef<Path>>(
+        file: P,
+        resolver: &CastResolver
+    ) -> Result<DirectorFile, DirpError> {
+        let movie_dir = match file.as_ref().parent() {
+            Some(dir) if dir != Path::new("") => dir,
+            _ => Path::new("."),
+        };
+
+        let loader = resolver.in_dir(movie_dir);
+
+        DirectorFile::from_reader(File::open(file.as_ref())?, &loader)
+    }
 
-        let mut file = File::open(file.as_ref())?;
+    // Read a movie from 'reader', starting at its current position,
+    // using 'loader' to load its external casts.
+    pub fn from_reader<R: Read>(
+        reader: R,
+        loader: &dyn CastLoader
+    ) -> Result<DirectorFile, DirpError> {
+        let mut base = DirectorFile::base_from_reader(reader)?;
 
         match base.header().endian() {
-            Endianness::Big => read_chunks::<File, BigEndian>(&mut base, &mut file),
-            Endianness::Little => read_chunks::<File, LittleEndian>(&mut base, &mut file),
+            Endianness::Big => read_chunks::<BigEndian>(&mut base, loader)?,
+            Endianness::Little => read_chunks::<LittleEndian>(&mut base, loader)?,
         }
 
         Ok(base)
@@ -84,8 +194,9 @@ impl DirectorFile {
         &self.header
     }
 
-    pub fn imap(&self) -> &InitialMap {
-        &self.imap
+    // Afterburner movies don't have an initial map.
+    pub fn imap(&self) -> Option<&InitialMap> {
+        self.imap.as_ref()
     }
 
     pub fn mmap(&self) -> &MemoryMap {
@@ -95,188 +206,500 @@ impl DirectorFile {
     pub fn key(&self) -> &KeyTable {
         &self.key
     }
-}
 
-// A helper function to make it easier to use the correct endianness.
-fn read_base_chunks<R: Read + Seek, E: endian::Endianness>(file: &mut R) -> (InitialMap, MemoryMap, KeyTable){
-    let imap = imap::read_imap::<R, E>(file);
+    // The cast libraries of the movie. These are read by DirectorFile::new,
+    // with_resolver and from_reader, but not by base or base_from_reader.
+    pub fn casts(&self) -> &Vec<CastLibrary> {
+        &self.casts
+    }
 
-    file.seek(SeekFrom::Start(imap.mmap_offset() as u64)).unwrap();
+    // Problems found while reading the movie that were skipped over.
+    pub fn warnings(&self) -> &Vec<Warning> {
+        &self.warnings
+    }
 
-    let mmap = mmap::read_mmap::<R, E>(file);
+    // Returns the cast library with the name 'name'.
+    pub fn cast(&self, name: &str) -> Option<&CastLibrary> {
+        self.casts.iter().find(|cast| cast.name() == name)
+    }
 
-    let entries = mmap.entries();
+    // Iterate over every chunk in the memory map.
+    pub fn chunks(&self) -> impl Iterator<Item = ChunkInfo<'_>> {
+        self.mmap.entries().iter().enumerate().map(move |(id, entry)| {
+            let id = id as u32;
+
+            ChunkInfo {
+                id,
+                chunk: entry.chunk(),
+                size: entry.size(),
+                offset: entry.offset(),
+                owner: self.key.owner(id),
+            }
+        })
+    }
 
-    let key_offset = entries.get(3).unwrap().offset();
+    // Returns the data of the chunk with the resource ID 'id',
+    // without the FourCC and size that precede it.
+    pub fn chunk_data(&self, id: u32) -> Result<&[u8], DirpError> {
+        self.chunks.data(&self.mmap, id)
+    }
 
-    file.seek(SeekFrom::Start(key_offset as u64)).unwrap();
+    // Returns the data of the first 'chunk' owned by 'owner'.
+    pub fn owned_chunk_data(&self, owner: u32, chunk: &[u8; 4]) -> Result<&[u8], DirpError> {
+        self.chunk_data(lookup(&self.key, owner, chunk)?)
+    }
 
-    let key = key::read_key::<R, E>(file);
+    // Read the configuration of the movie, from its DRCF or VWCF chunk.
+    pub fn config(&self) -> Result<Option<Config>, DirpError> {
+        let found = self.chunks().find_map(|chunk| match chunk.chunk() {
+            "DRCF" => Some((chunk.id(), b"DRCF")),
+            "VWCF" => Some((chunk.id(), b"VWCF")),
+            _ => None,
+        });
+
+        let (id, chunk) = match found {
+            Some(found) => found,
+            None => return Ok(None),
+        };
 
-    (imap, mmap, key)
-}
+        let mut file = self.chunk_reader(id)?;
 
-// Read dir/dxr chunks. The DirectorFile struct passed here must already
-// have parsed the base chunks.
-fn read_chunks<R: Read + Seek, E: endian::Endianness>(df: &mut DirectorFile, file: &mut R) {
-    let mmap_entries = df.mmap().entries();
-    let key = df.key();
-    let mcsl_offset = mmap_entries.get(
-        key.lookup(0x400, "MCsL".to_string()).unwrap() as usize
-    ).unwrap().offset();
+        let config = match self.header.endian() {
+            Endianness::Big => drcf::read_drcf::<_, BigEndian>(&mut file, chunk)?,
+            Endianness::Little => drcf::read_drcf::<_, LittleEndian>(&mut file, chunk)?,
+        };
 
-    file.seek(SeekFrom::Start(mcsl_offset as u64)).unwrap();
+        Ok(Some(config))
+    }
 
-    let mcsl = mcsl::read_mcsl::<R, E>(file);
+    // Read the score of the movie, from its VWSC chunk.
+    // Cast files don't have one.
+    pub fn score(&self) -> Result<Option<Score>, DirpError> {
+        let id = match self.mmap.entries().iter().position(|entry| entry.chunk() == "VWSC") {
+            Some(id) => id as u32,
+            None => return Ok(None),
+        };
 
-    for entry in mcsl.entries() {
-        if entry.name() == "Internal" {
-            continue;
-        }
+        let mut file = self.chunk_reader(id)?;
 
-        eprintln!("Parsing cast file {}", entry.name());
+        let score = match self.header.endian() {
+            Endianness::Big => vwsc::read_vwsc::<_, BigEndian>(&mut file)?,
+            Endianness::Little => vwsc::read_vwsc::<_, LittleEndian>(&mut file)?,
+        };
 
-        let path = format!("{}.cxt", entry.name());
-        let cast = DirectorFile::base(&path);
-        let cast = if let Ok(cast) = cast {
-            cast
-        } else {
-            // An error was returned when creating
-            // the DirectorFile, so we will skip reading it.
-            eprintln!("Couldn't parse file, skipping...");
-            continue
+        Ok(Some(score))
+    }
+
+    // Read the Lingo script in the Lscr chunk with the resource ID 'id'.
+    pub fn script(&self, id: u32) -> Result<Script, DirpError> {
+        // The handler records are larger in movies with an LctX
+        // script context than in those with an Lctx one.
+        let capital_x = self.mmap.entries().iter().any(|entry| entry.chunk() == "LctX");
+
+        self.read_script(id, capital_x)
+    }
+
+    // Read the script context owned by 'owner', which is the ID
+    // of a cast library. Casts without scripts don't have one.
+    pub fn script_context(&self, owner: u32) -> Result<Option<ScriptContext>, DirpError> {
+        let (id, chunk) = match self.key.lookup(owner, b"LctX") {
+            Some(id) => (id, b"LctX"),
+            None => match self.key.lookup(owner, b"Lctx") {
+                Some(id) => (id, b"Lctx"),
+                None => return Ok(None),
+            },
         };
 
-        let mut cast_file = File::open(&path).unwrap();
+        let mut file = self.chunk_reader(id)?;
 
-        let key = cast.key();
+        let context = match self.header.endian() {
+            Endianness::Big => lctx::read_lctx::<_, BigEndian>(&mut file, chunk)?,
+            Endianness::Little => lctx::read_lctx::<_, LittleEndian>(&mut file, chunk)?,
+        };
 
-        // Do a lookup for the id of the CAS* chunk
-        let cas_id = key.lookup(0x400, "CAS*".to_string()).unwrap();
-        let cas_offset = cast.mmap().entries().get(cas_id as usize).unwrap().offset();
+        Ok(Some(context))
+    }
 
-        cast_file.seek(SeekFrom::Start(cas_offset as u64)).unwrap();
+    // Read the names used by the scripts of 'context'.
+    pub fn name_table(&self, context: &ScriptContext) -> Result<NameTable, DirpError> {
+        let mut file = self.chunk_reader(context.lnam_id() as u32)?;
 
-        let cas = cas::read_cas::<File, E>(&mut cast_file);
+        match self.header.endian() {
+            Endianness::Big => lnam::read_lnam::<_, BigEndian>(&mut file),
+            Endianness::Little => lnam::read_lnam::<_, LittleEndian>(&mut file),
+        }
+    }
 
-        for &member in cas.members() {
-            if member == 0 {
-                continue;
-            }
+    // Read the script with the number 'number' in 'context'.
+    pub fn context_script(
+        &self,
+        context: &ScriptContext,
+        number: u32
+    ) -> Result<Option<Script>, DirpError> {
+        match context.script_id(number) {
+            Some(id) => Ok(Some(self.read_script(id, context.capital_x())?)),
+            None => Ok(None),
+        }
+    }
 
-            let member_offset = cast.mmap().entries().get(member as usize).unwrap().offset();
+    // Read every Lingo script in the movie, with the resource IDs
+    // of their Lscr chunks.
+    pub fn scripts(&self) -> Result<Vec<(u32, Script)>, DirpError> {
+        self.chunks()
+            .filter(|chunk| chunk.chunk() == "Lscr")
+            .map(|chunk| Ok((chunk.id(), self.script(chunk.id())?)))
+            .collect()
+    }
 
-            cast_file.seek(SeekFrom::Start(member_offset as u64)).unwrap();
+    fn read_script(&self, id: u32, capital_x: bool) -> Result<Script, DirpError> {
+        // NOTE Movies without a configuration are assumed to be from
+        // Director 5, the oldest version with the 32-bit literal types.
+        let version = self.config()?.map_or(500, |config| config.version());
 
-            let cast_properties = cast::read_cast::<File, E>(&mut cast_file);
+        let mut file = self.chunk_reader(id)?;
 
-            match cast_properties.kind() {
-                CastKind::Bitmap => {
-                    // Bitmaps own the BITD chunk
-                    let id = match cast.key().lookup(member, "BITD".to_string()) {
-                        Some(x) => x,
-                        // TODO For some reason, some of the bitmap casts
-                        // doesn't own a BITD chunk...?
-                        None => continue,
-                    };
-                    let offset = cast.mmap().entries().get(id as usize).unwrap().offset();
+        match self.header.endian() {
+            Endianness::Big => lscr::read_lscr::<_, BigEndian>(&mut file, capital_x, version),
+            Endianness::Little => lscr::read_lscr::<_, LittleEndian>(&mut file, capital_x, version),
+        }
+    }
 
-                    cast_file.seek(SeekFrom::Start(offset as u64)).unwrap();
+    // Returns a reader positioned at the start of the chunk with
+    // the resource ID 'id'.
+    fn chunk_reader(&self, id: u32) -> Result<Cursor<&[u8]>, DirpError> {
+        self.chunks.reader(&self.mmap, id)
+    }
+}
 
-                    let data = bitd::read_bitd::<File, E>(&mut cast_file);
+pub struct ChunkInfo<'a> {
+    id: u32,
+    chunk: &'a str,
+    size: u32,
+    offset: u32,
+    owner: Option<u32>,
+}
 
-                    let &depth = cast_properties.properties()
-                        .get(&CastProperty::BitmapDepth).unwrap()
-                        .downcast_ref::<usize>().unwrap();
+impl<'a> ChunkInfo<'a> {
+    // The resource ID of the chunk.
+    pub fn id(&self) -> u32 {
+        self.id
+    }
 
-                    // The parser can only hande a bit depth of 32.
-                    if depth == 32 {
-                        let bitmap = parse_bitmap_data(&cast_properties, data);
-                    } else {
-                        eprintln!("Can only handle bitmaps with bit depth of 32");
-                    }
-                },
-                _ => {
-                    eprintln!("This cast type is not supported, skipping")
-                }
-            }
+    // The FourCC of the chunk.
+    pub fn chunk(&self) -> &'a str {
+        self.chunk
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    // For Afterburner movies this is the offset in the Afterburner map.
+    pub fn offset(&self) -> u32 {
+        self.offset
+    }
+
+    // The resource ID of the owner of the chunk, from the key table.
+    pub fn owner(&self) -> Option<u32> {
+        self.owner
+    }
+}
+
+impl Chunks {
+    fn reader(&self, mmap: &MemoryMap, id: u32) -> Result<Cursor<&[u8]>, DirpError> {
+        match self {
+            Chunks::File(data) => {
+                let mut reader = Cursor::new(&data[..]);
+                reader.set_position(mmap.entry(id)?.offset() as u64);
+
+                Ok(reader)
+            },
+            Chunks::Decompressed(chunks) => {
+                let data = chunks.get(&id).ok_or(DirpError::MissingChunk(id))?;
+
+                Ok(Cursor::new(&data[..]))
+            },
+        }
+    }
+
+    fn data(&self, mmap: &MemoryMap, id: u32) -> Result<&[u8], DirpError> {
+        match self {
+            Chunks::File(data) => {
+                let entry = mmap.entry(id)?;
+                let start = entry.offset() as usize + 8;
+                let end = start + entry.size() as usize;
+
+                data.get(start..end).ok_or(DirpError::Truncated)
+            },
+            Chunks::Decompressed(chunks) => {
+                let data = chunks.get(&id).ok_or(DirpError::MissingChunk(id))?;
+
+                Ok(&data[8..])
+            },
         }
     }
 }
 
-// NOTE We assume that the bit depth is 32
-fn parse_bitmap_data(properties: &CastProperties, data: BitmapData) -> Vec<Vec<[u8; 4]>> {
-    let &width = properties.properties()
-        .get(&CastProperty::BitmapWidth).unwrap()
-        .downcast_ref::<usize>().unwrap();
-    let &height = properties.properties()
-        .get(&CastProperty::BitmapHeight).unwrap()
-        .downcast_ref::<usize>().unwrap();
+// A helper function to make it easier to use the correct endianness.
+fn read_base_chunks<E: endian::Endianness>(
+    header: &Header,
+    mut file: Cursor<Vec<u8>>,
+    warnings: &mut Vec<Warning>
+) -> Result<(Option<InitialMap>, MemoryMap, Chunks), DirpError> {
+    if header.is_afterburner() {
+        let (mmap, chunks) = read_afterburner_chunks::<_, E>(&mut file, warnings)?;
+
+        return Ok((None, mmap, Chunks::Decompressed(chunks)));
+    }
 
-    let mut bitmap = vec![vec![[0u8, 0u8, 0u8, 255u8]; width]; height];
+    let imap = imap::read_imap::<_, E>(&mut file)?;
 
-    let mut x = 0;
-    let mut y = 0;
-    let mut c = 0;
+    file.seek(SeekFrom::Start(imap.mmap_offset() as u64))?;
 
-    let mut data = VecDeque::from(data.data().clone());
+    let mmap = mmap::read_mmap::<_, E>(&mut file)?;
 
-    while !data.is_empty() {
-        let len = data.pop_front().unwrap();
+    Ok((Some(imap), mmap, Chunks::File(file.into_inner())))
+}
 
-        // NOTE This value is from Shockky. MrBrax use >= 129
-        if len >= 128 {
-            let len = 257 - len as u16;
-            let len = len as u8;
+// The largest resource ID accepted in an Afterburner map. Movies have
+// at most a few thousand chunks, so this leaves plenty of room.
+const MAX_RESOURCE_ID: u32 = 0x10_0000;
+
+// Read the Afterburner map and decompress all of the chunks in the movie.
+// The returned memory map has an entry for every resource ID, just like
+// the memory map of an uncompressed movie.
+fn read_afterburner_chunks<R: Read + Seek, E: endian::Endianness>(
+    file: &mut R,
+    warnings: &mut Vec<Warning>
+) -> Result<(MemoryMap, HashMap<u32, Vec<u8>>), DirpError> {
+    let _fver = fver::read_fver::<R, E>(file)?;
+    let fcdr = fcdr::read_fcdr::<R, E>(file)?;
+    let abmp = abmp::read_abmp::<R, E>(file)?;
+    let fgei = fgei::read_fgei::<R, E>(file, &abmp)?;
+
+    let body_offset = fgei.body_offset();
+    let mut ils = fgei.into_chunks();
+
+    let mut entries = Vec::new();
+    let mut chunks = HashMap::new();
+
+    for entry in abmp.entries() {
+        // The memory map is filled up to the largest ID,
+        // so a corrupt ID could otherwise exhaust memory.
+        if entry.id() > MAX_RESOURCE_ID {
+            return Err(DirpError::InvalidResourceId(entry.id()));
+        }
 
-            let b = data.pop_front().unwrap();
+        let id = entry.id() as usize;
 
-            for _ in 0..len {
-                bitmap[y][x][c] = b;
+        while entries.len() <= id {
+            entries.push(MemoryMapEntry::new("free".to_string(), 0, 0));
+        }
+
+        // Chunks in the initial load segment may not have an offset,
+        // which is stored as -1.
+        let offset = u32::try_from(entry.offset()).ok();
+
+        entries[id] = MemoryMapEntry::new(
+            entry.chunk().to_string(),
+            entry.size(),
+            offset.unwrap_or(0)
+        );
+
+        let data = if let Some(data) = ils.remove(&entry.id()) {
+            data
+        } else if entry.chunk() == "ILS " {
+            // The initial load segment has already been read.
+            continue;
+        } else {
+            let offset = match offset {
+                Some(offset) => body_offset + offset as u64,
+                None => {
+                    warnings.push(Warning::InvalidChunkOffset {
+                        id: entry.id(),
+                        offset: entry.offset(),
+                    });
+                    continue;
+                },
+            };
 
-                x += 1;
-                if x >= width {
-                    c += 1;
-                    c %= 4;
+            file.seek(SeekFrom::Start(offset))?;
 
-                    x = 0;
+            let mut data = vec![0; entry.compressed_size() as usize];
+            file.read_exact(&mut data)?;
 
-                    if c == 0 {
-                        y += 1;
-                    }
-                }
+            let compression = fcdr.entries().get(entry.compression() as usize);
 
-                if y >= height {
-                    return bitmap;
-                }
+            match compression.map(|compression| compression.id()) {
+                Some(CompressionId::ZLIB) => chunk::decompress(&data)?,
+                _ => data,
             }
-        } else {
-            let len = len + 1;
+        };
 
-            for _ in 0..len {
-                let b = data.pop_front().unwrap();
+        chunks.insert(entry.id(), add_chunk_header::<E>(entry.chunk(), data));
+    }
 
-                bitmap[y][x][c] = b;
+    Ok((MemoryMap::new(entries), chunks))
+}
 
-                x += 1;
+// Prepend the FourCC and size to the data of a chunk,
+// in the same way as they are stored in an uncompressed movie.
+fn add_chunk_header<E: endian::Endianness>(chunk: &str, data: Vec<u8>) -> Vec<u8> {
+    let mut fourcc = Vec::from(chunk.as_bytes());
+    let mut size = (data.len() as u32).to_be_bytes();
 
-                if x >= width {
-                    c += 1;
-                    c %= 4;
+    if !E::is_big_endian() {
+        fourcc.reverse();
+        size.reverse();
+    }
 
-                    x = 0;
+    let mut chunk = Vec::with_capacity(data.len() + 8);
+    chunk.extend(fourcc);
+    chunk.extend(&size);
+    chunk.extend(data);
 
-                    if c == 0 {
-                        y += 1;
-                    }
-                }
+    chunk
+}
 
-                if y >= height {
-                    return bitmap;
-                }
-            }
+// Read dir/dxr chunks. The DirectorFile struct passed here must already
+// have parsed the base chunks.
+fn read_chunks<E: endian::Endianness>(
+    df: &mut DirectorFile,
+    loader: &dyn CastLoader
+) -> Result<(), DirpError> {
+    let mcsl = match df.key().lookup(0x400, b"MCsL") {
+        Some(mcsl_id) => mcsl::read_mcsl::<_, E>(&mut df.chunk_reader(mcsl_id)?)?,
+        // Movies without a cast list have a single internal cast.
+        None => MovieCastList::internal(),
+    };
+
+    let mut casts = Vec::new();
+    let mut warnings = Vec::new();
+
+    for (index, entry) in mcsl.entries().iter().enumerate() {
+        let number = index as u32 + 1;
+
+        // The members of an internal cast are stored in the movie itself,
+        // in a CAS* chunk owned by the ID of the cast library.
+        if entry.path().is_empty() {
+            log::debug!("Parsing internal cast {}", entry.name());
+
+            let library = cast_library::read_cast_library(
+                df, number, entry, entry.id(), &mut warnings
+            )?;
+
+            casts.push(library);
+            continue;
+        }
+
+        log::debug!("Parsing cast file {}", entry.name());
+
+        let cast = match loader.load(entry) {
+            Some(Ok(cast)) => cast,
+            None => {
+                warnings.push(Warning::UnresolvedCast {
+                    name: entry.name().clone(),
+                    path: entry.path().clone(),
+                });
+                continue
+            },
+            Some(Err(error)) => {
+                // An error was returned when creating
+                // the DirectorFile, so we will skip reading it.
+                warnings.push(Warning::UnreadableCast {
+                    name: entry.name().clone(),
+                    error,
+                });
+                continue
+            },
+        };
+
+        // The members of an external cast are owned by the
+        // first (and only) cast library in the cast file.
+        let library = cast_library::read_cast_library(
+            &cast, number, entry, 0x400, &mut warnings
+        )?;
+
+        casts.push(library);
+    }
+
+    cast_library::decode_bitmaps(&mut casts, &mut warnings)?;
+
+    df.casts = casts;
+    df.warnings.append(&mut warnings);
+
+    Ok(())
+}
+
+// Look up the resource ID of 'chunk' owned by 'owner',
+// or return an error if there is no such key.
+fn lookup(key: &KeyTable, owner: u32, chunk: &[u8; 4]) -> Result<u32, DirpError> {
+    key.lookup(owner, chunk).ok_or(DirpError::MissingKey {
+        owner,
+        chunk: *chunk,
+    })
+}
+
+// Get a property of a cast member, or return an error if the
+// member doesn't have it.
+fn property<T: 'static>(
+    properties: &CastProperties,
+    property: CastProperty
+) -> Result<&T, DirpError> {
+    properties.properties()
+        .get(&property)
+        .and_then(|value| value.downcast_ref::<T>())
+        .ok_or(DirpError::MissingProperty(property))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn from_reader() {
+        let movie = test_movie::movie(
+            &[(b"MCsL", test_movie::mcsl(&[("Shared", "C:\\Movie\\Shared.cxt", 1)]))],
+            &[(4, 0x400, b"MCsL")],
+        );
+
+        // The movie is read from the current position of the reader
+        let mut data = b"junk".to_vec();
+        data.extend(&movie);
+
+        let mut reader = Cursor::new(data);
+        reader.set_position(4);
+
+        let loader = |_: &mcsl::Cast| None;
+        let df = DirectorFile::from_reader(reader, &loader).unwrap();
+
+        assert_eq!(df.mmap().entries().len(), 5);
+        assert_eq!(df.key().lookup(0x400, b"MCsL"), Some(4));
+        assert!(df.casts().is_empty());
+
+        match &df.warnings()[..] {
+            [Warning::UnresolvedCast { name, path }] => {
+                assert_eq!(name, "Shared");
+                assert_eq!(path, "C:\\Movie\\Shared.cxt");
+            },
+            warnings => panic!("unexpected warnings: {:?}", warnings),
         }
     }
 
-    bitmap
+    #[test]
+    fn missing_key_table() {
+        let mut movie = test_movie::movie(&[], &[]);
+
+        // Rename the KEY* chunk in the memory map
+        let key = movie.windows(4).rposition(|w| w == b"KEY*").unwrap();
+        let entry = movie[..key].windows(4).rposition(|w| w == b"KEY*").unwrap();
+        movie[entry..entry + 4].copy_from_slice(b"junk");
+
+        assert!(matches!(
+            DirectorFile::base_from_reader(Cursor::new(movie)),
+            Err(DirpError::MissingKeyTable)
+        ));
+    }
 }
@@ -0,0 +1,4 @@
+pub mod opcode;
+pub mod disassembler;
+pub mod ast;
+pub mod decompiler;
@@ -0,0 +1,434 @@
+use std::fmt::Write;
+
+// The syntax to write decompiled Lingo in.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Syntax {
+    // The English-like syntax of Director 4 and up,
+    // as in "the locH of sprite 1".
+    Verbose,
+    // The dot syntax of Director 7 and up, as in "sprite(1).locH".
+    Dot,
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BinaryOp {
+    Mul,
+    Add,
+    Sub,
+    Div,
+    Mod,
+    JoinStr,
+    JoinPadStr,
+    Lt,
+    LtEq,
+    NtEq,
+    Eq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    ContainsStr,
+    Contains0Str,
+    // The left operand of these is always a sprite.
+    Intersects,
+    Within,
+}
+
+impl BinaryOp {
+    fn symbol(self) -> &'static str {
+        match self {
+            BinaryOp::Mul => "*",
+            BinaryOp::Add => "+",
+            BinaryOp::Sub => "-",
+            BinaryOp::Div => "/",
+            BinaryOp::Mod => "mod",
+            BinaryOp::JoinStr => "&",
+            BinaryOp::JoinPadStr => "&&",
+            BinaryOp::Lt => "<",
+            BinaryOp::LtEq => "<=",
+            BinaryOp::NtEq => "<>",
+            BinaryOp::Eq => "=",
+            BinaryOp::Gt => ">",
+            BinaryOp::GtEq => ">=",
+            BinaryOp::And => "and",
+            BinaryOp::Or => "or",
+            BinaryOp::ContainsStr => "contains",
+            BinaryOp::Contains0Str => "starts",
+            BinaryOp::Intersects => "intersects",
+            BinaryOp::Within => "within",
+        }
+    }
+
+    // Operators with a higher precedence bind tighter.
+    fn precedence(self) -> u8 {
+        match self {
+            BinaryOp::And | BinaryOp::Or => 1,
+            BinaryOp::Lt | BinaryOp::LtEq | BinaryOp::NtEq | BinaryOp::Eq |
+            BinaryOp::Gt | BinaryOp::GtEq | BinaryOp::ContainsStr |
+            BinaryOp::Contains0Str | BinaryOp::Intersects | BinaryOp::Within => 2,
+            BinaryOp::JoinStr | BinaryOp::JoinPadStr => 3,
+            BinaryOp::Add | BinaryOp::Sub => 4,
+            BinaryOp::Mul | BinaryOp::Div | BinaryOp::Mod => 5,
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ChunkType {
+    Char,
+    Word,
+    Item,
+    Line,
+}
+
+impl ChunkType {
+    pub(crate) fn from_id(id: i32) -> Option<ChunkType> {
+        match id {
+            1 => Some(ChunkType::Char),
+            2 => Some(ChunkType::Word),
+            3 => Some(ChunkType::Item),
+            4 => Some(ChunkType::Line),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            ChunkType::Char => "char",
+            ChunkType::Word => "word",
+            ChunkType::Item => "item",
+            ChunkType::Line => "line",
+        }
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PutType {
+    Into,
+    After,
+    Before,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Expr {
+    Int(i32),
+    Float(f64),
+    String(String),
+    Symbol(String),
+    Var(String),
+    List(Vec<Expr>),
+    PropList(Vec<(Expr, Expr)>),
+    // The arguments of a call, which is a statement if 'no_ret' is set.
+    ArgList(Vec<Expr>, bool),
+    Binary(BinaryOp, Box<Expr>, Box<Expr>),
+    Neg(Box<Expr>),
+    Not(Box<Expr>),
+    Call(String, Vec<Expr>),
+    // A call of a method, where the first argument is the object.
+    ObjCall(String, Vec<Expr>),
+    // A property of an object, as in "the locH of sprite 1".
+    Prop(Box<Expr>, String),
+    // A property of the movie or the system, as in "the timer".
+    The(String),
+    Sprite(Box<Expr>),
+    // A member, field or other numbered object, such as a sound or menu,
+    // with an optional cast library.
+    Member(&'static str, Box<Expr>, Option<Box<Expr>>),
+    // A menu item, followed by its menu.
+    MenuItem(Box<Expr>, Box<Expr>),
+    Chunk(ChunkType, Box<Expr>, Box<Expr>, Box<Expr>),
+    LastChunk(ChunkType, Box<Expr>),
+    ChunkCount(ChunkType, Box<Expr>),
+    // Something the decompiler doesn't understand.
+    Unknown(String),
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum Stmt {
+    Expr(Expr),
+    Assign(Expr, Expr),
+    Put(PutType, Expr, Expr),
+    Delete(Expr),
+    Hilite(Expr),
+    Return(Option<Expr>),
+    Exit,
+    ExitRepeat,
+    NextRepeat,
+    If(Expr, Vec<Stmt>, Vec<Stmt>),
+    RepeatWhile(Expr, Vec<Stmt>),
+    // A loop over a variable from the first to the second value,
+    // which counts down if the bool is set.
+    RepeatWith(String, Expr, Expr, bool, Vec<Stmt>),
+    Tell(Expr, Vec<Stmt>),
+    Comment(String),
+}
+
+pub(crate) fn write_stmts(out: &mut String, stmts: &[Stmt], indent: usize, syntax: Syntax) {
+    for stmt in stmts {
+        write_stmt(out, stmt, indent, syntax);
+    }
+}
+
+fn write_stmt(out: &mut String, stmt: &Stmt, indent: usize, syntax: Syntax) {
+    let pad = "  ".repeat(indent);
+
+    match stmt {
+        Stmt::Expr(expr) => {
+            let _ = writeln!(out, "{}{}", pad, statement_call(expr, syntax));
+        },
+        Stmt::Assign(target @ Expr::Var(_), value) => {
+            let _ = writeln!(out, "{}{} = {}", pad, expr(target, syntax), expr(value, syntax));
+        },
+        Stmt::Assign(target, value) => match syntax {
+            Syntax::Verbose => {
+                let _ = writeln!(out, "{}set {} to {}", pad, expr(target, syntax), expr(value, syntax));
+            },
+            Syntax::Dot => {
+                let _ = writeln!(out, "{}{} = {}", pad, expr(target, syntax), expr(value, syntax));
+            },
+        },
+        Stmt::Put(kind, target, value) => {
+            let kind = match kind {
+                PutType::Into => "into",
+                PutType::After => "after",
+                PutType::Before => "before",
+            };
+
+            let _ = writeln!(out, "{}put {} {} {}", pad, expr(value, syntax), kind, expr(target, syntax));
+        },
+        Stmt::Delete(target) => {
+            let _ = writeln!(out, "{}delete {}", pad, expr(target, syntax));
+        },
+        Stmt::Hilite(target) => {
+            let _ = writeln!(out, "{}hilite {}", pad, expr(target, syntax));
+        },
+        Stmt::Return(Some(value)) => {
+            let _ = writeln!(out, "{}return {}", pad, expr(value, syntax));
+        },
+        Stmt::Return(None) => {
+            let _ = writeln!(out, "{}return", pad);
+        },
+        Stmt::Exit => {
+            let _ = writeln!(out, "{}exit", pad);
+        },
+        Stmt::ExitRepeat => {
+            let _ = writeln!(out, "{}exit repeat", pad);
+        },
+        Stmt::NextRepeat => {
+            let _ = writeln!(out, "{}next repeat", pad);
+        },
+        Stmt::If(..) => {
+            write_if(out, stmt, indent, syntax, false);
+            let _ = writeln!(out, "{}end if", pad);
+        },
+        Stmt::RepeatWhile(condition, body) => {
+            let _ = writeln!(out, "{}repeat while {}", pad, expr(condition, syntax));
+            write_stmts(out, body, indent + 1, syntax);
+            let _ = writeln!(out, "{}end repeat", pad);
+        },
+        Stmt::RepeatWith(var, start, end, down, body) => {
+            let to = if *down { "down to" } else { "to" };
+
+            let _ = writeln!(
+                out, "{}repeat with {} = {} {} {}",
+                pad, var, expr(start, syntax), to, expr(end, syntax)
+            );
+            write_stmts(out, body, indent + 1, syntax);
+            let _ = writeln!(out, "{}end repeat", pad);
+        },
+        Stmt::Tell(window, body) => {
+            let _ = writeln!(out, "{}tell {}", pad, expr(window, syntax));
+            write_stmts(out, body, indent + 1, syntax);
+            let _ = writeln!(out, "{}end tell", pad);
+        },
+        Stmt::Comment(comment) => {
+            let _ = writeln!(out, "{}-- {}", pad, comment);
+        },
+    }
+}
+
+// Write an if statement, turning an else branch that is only
+// another if statement into "else if".
+fn write_if(out: &mut String, stmt: &Stmt, indent: usize, syntax: Syntax, chained: bool) {
+    let pad = "  ".repeat(indent);
+
+    if let Stmt::If(condition, then, otherwise) = stmt {
+        let keyword = if chained { "else if" } else { "if" };
+
+        let _ = writeln!(out, "{}{} {} then", pad, keyword, expr(condition, syntax));
+        write_stmts(out, then, indent + 1, syntax);
+
+        match otherwise.as_slice() {
+            [] => {},
+            [nested @ Stmt::If(..)] => write_if(out, nested, indent, syntax, true),
+            otherwise => {
+                let _ = writeln!(out, "{}else", pad);
+                write_stmts(out, otherwise, indent + 1, syntax);
+            },
+        }
+    }
+}
+
+// Calls used as statements are written without parentheses
+// in the verbose syntax, as in "go to frame 1".
+fn statement_call(call: &Expr, syntax: Syntax) -> String {
+    match (call, syntax) {
+        (Expr::Call(name, args), Syntax::Verbose) if !args.is_empty() => {
+            format!("{} {}", name, list(args, syntax))
+        },
+        (call, syntax) => expr(call, syntax),
+    }
+}
+
+fn list(exprs: &[Expr], syntax: Syntax) -> String {
+    exprs.iter().map(|e| expr(e, syntax)).collect::<Vec<_>>().join(", ")
+}
+
+pub(crate) fn expr(e: &Expr, syntax: Syntax) -> String {
+    match e {
+        Expr::Int(int) => int.to_string(),
+        Expr::Float(float) => {
+            if float.fract() == 0.0 && float.is_finite() {
+                format!("{:.1}", float)
+            } else {
+                float.to_string()
+            }
+        },
+        Expr::String(string) => string_literal(string),
+        Expr::Symbol(symbol) => format!("#{}", symbol),
+        Expr::Var(name) => name.clone(),
+        Expr::List(items) => format!("[{}]", list(items, syntax)),
+        Expr::PropList(items) if items.is_empty() => "[:]".to_string(),
+        Expr::PropList(items) => {
+            let items = items.iter()
+                .map(|(key, value)| format!("{}: {}", expr(key, syntax), expr(value, syntax)))
+                .collect::<Vec<_>>();
+
+            format!("[{}]", items.join(", "))
+        },
+        Expr::ArgList(args, _) => list(args, syntax),
+        Expr::Binary(op, left, right) => {
+            format!(
+                "{} {} {}",
+                operand(left, op.precedence(), false, syntax),
+                op.symbol(),
+                operand(right, op.precedence(), true, syntax)
+            )
+        },
+        Expr::Neg(value) => format!("-{}", operand(value, 6, false, syntax)),
+        Expr::Not(value) => format!("not {}", operand(value, 6, false, syntax)),
+        Expr::Call(name, args) => format!("{}({})", name, list(args, syntax)),
+        Expr::ObjCall(name, args) => match (syntax, args.split_first()) {
+            (Syntax::Dot, Some((object, args))) => {
+                format!("{}.{}({})", expr(object, syntax), name, list(args, syntax))
+            },
+            _ => format!("{}({})", name, list(args, syntax)),
+        },
+        Expr::Prop(object, name) => match syntax {
+            Syntax::Verbose => format!("the {} of {}", name, expr(object, syntax)),
+            Syntax::Dot => format!("{}.{}", expr(object, syntax), name),
+        },
+        Expr::The(name) => format!("the {}", name),
+        Expr::Sprite(number) => match syntax {
+            Syntax::Verbose => format!("sprite {}", operand(number, 6, false, syntax)),
+            Syntax::Dot => format!("sprite({})", expr(number, syntax)),
+        },
+        Expr::Member(kind, member, cast) => match (syntax, cast) {
+            (Syntax::Verbose, None) => format!("{} {}", kind, operand(member, 6, false, syntax)),
+            (Syntax::Verbose, Some(cast)) => {
+                format!(
+                    "{} {} of castLib {}",
+                    kind, operand(member, 6, false, syntax), operand(cast, 6, false, syntax)
+                )
+            },
+            (Syntax::Dot, None) => format!("{}({})", kind, expr(member, syntax)),
+            (Syntax::Dot, Some(cast)) => {
+                format!("{}({}, {})", kind, expr(member, syntax), expr(cast, syntax))
+            },
+        },
+        Expr::MenuItem(item, menu) => {
+            format!(
+                "menuItem {} of menu {}",
+                operand(item, 6, false, syntax), operand(menu, 6, false, syntax)
+            )
+        },
+        Expr::Chunk(kind, first, last, string) => match syntax {
+            Syntax::Verbose => {
+                let range = if last.as_ref() == &Expr::Int(0) || last == first {
+                    expr(first, syntax)
+                } else {
+                    format!("{} to {}", expr(first, syntax), expr(last, syntax))
+                };
+
+                format!("{} {} of {}", kind.name(), range, expr(string, syntax))
+            },
+            Syntax::Dot => {
+                let range = if last.as_ref() == &Expr::Int(0) || last == first {
+                    expr(first, syntax)
+                } else {
+                    format!("{}..{}", expr(first, syntax), expr(last, syntax))
+                };
+
+                format!("{}.{}[{}]", operand(string, 6, false, syntax), kind.name(), range)
+            },
+        },
+        Expr::LastChunk(kind, string) => {
+            format!("the last {} of {}", kind.name(), expr(string, syntax))
+        },
+        Expr::ChunkCount(kind, string) => match syntax {
+            Syntax::Verbose => format!("the number of {}s in {}", kind.name(), expr(string, syntax)),
+            Syntax::Dot => format!("{}.{}.count", operand(string, 6, false, syntax), kind.name()),
+        },
+        Expr::Unknown(text) => format!("ERROR({})", text),
+    }
+}
+
+// Write the operand of an operator with the precedence 'precedence',
+// adding parentheses where they are needed.
+fn operand(e: &Expr, precedence: u8, right: bool, syntax: Syntax) -> String {
+    let needs_parens = match e {
+        Expr::Binary(op, _, _) => {
+            op.precedence() < precedence || (right && op.precedence() == precedence)
+        },
+        Expr::Neg(_) | Expr::Not(_) => precedence >= 6,
+        _ => false,
+    };
+
+    if needs_parens {
+        format!("({})", expr(e, syntax))
+    } else {
+        expr(e, syntax)
+    }
+}
+
+// Lingo strings can't escape quotes or line breaks,
+// so they are joined in with the QUOTE and RETURN constants.
+fn string_literal(string: &str) -> String {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+
+    for c in string.chars() {
+        let constant = match c {
+            '"' => "QUOTE",
+            '\r' => "RETURN",
+            '\t' => "TAB",
+            _ => {
+                current.push(c);
+                continue;
+            },
+        };
+
+        if !current.is_empty() {
+            parts.push(format!("\"{}\"", current));
+            current.clear();
+        }
+
+        parts.push(constant.to_string());
+    }
+
+    if !current.is_empty() || parts.is_empty() {
+        parts.push(format!("\"{}\"", current));
+    }
+
+    parts.join(" & ")
+}
@@ -0,0 +1,982 @@
+use std::convert::TryFrom;
+
+use crate::DirpError;
+
+use crate::chunk::lscr::{Handler, Literal, Script};
+use crate::chunk::lnam::NameTable;
+
+use super::ast::{self, BinaryOp, ChunkType, Expr, PutType, Stmt, Syntax};
+use super::disassembler::{self, Instruction, Reference};
+use super::opcode::Opcode;
+
+// The properties of "the" that are read and set with the get and set
+// opcodes, by property type. Properties we don't know the name of are
+// written as "unknownProperty<ID>".
+const MOVIE_PROPERTIES: [&str; 12] = [
+    "floatPrecision", "mouseDownScript", "mouseUpScript", "keyDownScript",
+    "keyUpScript", "timeoutScript", "short time", "abbr time",
+    "long time", "short date", "abbr date", "long date",
+];
+
+const MENU_PROPERTIES: [&str; 3] = ["", "name", "number of menuItems"];
+
+const MENU_ITEM_PROPERTIES: [&str; 5] = ["", "name", "checkMark", "enabled", "script"];
+
+const SOUND_PROPERTIES: [&str; 2] = ["", "volume"];
+
+const SPRITE_PROPERTIES: [&str; 43] = [
+    "", "type", "backColor", "bottom", "castNum", "constraint", "cursor",
+    "foreColor", "height", "immediate", "ink", "left", "lineSize", "locH",
+    "locV", "movieRate", "movieTime", "pattern", "puppet", "right",
+    "startTime", "stopTime", "stretch", "top", "trails", "visible", "volume",
+    "width", "blend", "scriptNum", "moveableSprite", "editableText",
+    "scoreColor", "loc", "rect", "memberNum", "castLibNum", "member",
+    "scriptInstanceList", "currentTime", "mostRecentCuePoint", "tweened",
+    "name",
+];
+
+const ANIMATION_PROPERTIES: [&str; 41] = [
+    "", "beepOn", "buttonStyle", "centerStage", "checkBoxAccess",
+    "checkboxType", "colorDepth", "colorQD", "exitLock", "fixStageSize",
+    "fullColorPermit", "imageDirect", "doubleClick", "key", "lastClick",
+    "lastEvent", "keyCode", "lastKey", "lastRoll", "timeoutLapsed",
+    "multiSound", "pauseState", "quickTimePresent", "selEnd", "selStart",
+    "soundEnabled", "soundLevel", "stageColor", "stillDown",
+    "switchColorDepth", "timeoutKeyDown", "timeoutLength", "timeoutMouse",
+    "timeoutPlay", "timer", "preLoadRAM", "videoForWindowsPresent",
+    "netPresent", "safePlayer", "soundKeepDevice", "soundMixMedia",
+];
+
+const ANIMATION2_PROPERTIES: [&str; 6] = [
+    "", "perFrameHook", "number of castMembers", "number of menus",
+    "number of castLibs", "number of xtras",
+];
+
+const MEMBER_PROPERTIES: [&str; 20] = [
+    "", "name", "text", "textStyle", "textFont", "textHeight", "textAlign",
+    "textSize", "picture", "hilite", "number", "size", "loop", "duration",
+    "controller", "directToStage", "sound", "foreColor", "backColor", "type",
+];
+
+// Decompile the bytecode of 'handler', which is a handler of 'script',
+// into Lingo statements.
+pub fn decompile(
+    handler: &Handler,
+    script: &Script,
+    names: &NameTable
+) -> Result<Vec<Stmt>, DirpError> {
+    let instructions = disassembler::disassemble(handler, script, names)?;
+
+    let mut decompiler = Decompiler {
+        instructions,
+        handler,
+        script,
+        names,
+        loops: Vec::new(),
+    };
+
+    let end = decompiler.instructions.len();
+
+    Ok(decompiler.block(0, end))
+}
+
+// Decompile 'handler' into Lingo source, from "on" to "end".
+pub fn decompile_handler(
+    handler: &Handler,
+    script: &Script,
+    names: &NameTable,
+    syntax: Syntax
+) -> Result<String, DirpError> {
+    let stmts = decompile(handler, script, names)?;
+
+    let name = names.name(handler.name_id()).unwrap_or("UNKNOWN");
+
+    let arguments = handler.arguments().iter()
+        .map(|&id| names.name(id).unwrap_or("UNKNOWN"))
+        .collect::<Vec<_>>();
+
+    let mut out = String::new();
+
+    if arguments.is_empty() {
+        out.push_str(&format!("on {}\n", name));
+    } else {
+        out.push_str(&format!("on {} {}\n", name, arguments.join(", ")));
+    }
+
+    ast::write_stmts(&mut out, &stmts, 1, syntax);
+    out.push_str("end\n");
+
+    Ok(out)
+}
+
+// Decompile all of 'script' into Lingo source, starting with the
+// properties and globals that it declares.
+pub fn decompile_script(
+    script: &Script,
+    names: &NameTable,
+    syntax: Syntax
+) -> Result<String, DirpError> {
+    let mut out = String::new();
+
+    let declare = |keyword: &str, ids: &Vec<i16>| -> String {
+        let names = ids.iter()
+            .map(|&id| names.name(id).unwrap_or("UNKNOWN"))
+            .collect::<Vec<_>>();
+
+        format!("{} {}\n", keyword, names.join(", "))
+    };
+
+    if !script.properties().is_empty() {
+        out.push_str(&declare("property", script.properties()));
+    }
+
+    if !script.globals().is_empty() {
+        out.push_str(&declare("global", script.globals()));
+    }
+
+    for handler in script.handlers() {
+        if !out.is_empty() {
+            out.push('\n');
+        }
+
+        out.push_str(&decompile_handler(handler, script, names, syntax)?);
+    }
+
+    Ok(out)
+}
+
+struct Decompiler<'a> {
+    instructions: Vec<Instruction>,
+    handler: &'a Handler,
+    script: &'a Script,
+    names: &'a NameTable,
+    // The offsets of the start and end of the loops we are in.
+    loops: Vec<(usize, usize)>,
+}
+
+impl<'a> Decompiler<'a> {
+    // Decompile the instructions from index 'start' up to 'end'.
+    fn block(&mut self, start: usize, end: usize) -> Vec<Stmt> {
+        let mut stmts = Vec::new();
+        let mut stack = Vec::new();
+        let mut i = start;
+
+        while i < end {
+            let instruction = self.instructions[i].clone();
+            let operand = instruction.operand().unwrap_or(0);
+
+            i += 1;
+
+            match instruction.opcode() {
+                Opcode::Ret | Opcode::RetFactory => {
+                    // Every handler ends with a return
+                    let last = i == self.instructions.len();
+                    let returned = matches!(stmts.last(), Some(Stmt::Return(_)));
+
+                    if !last && !returned {
+                        stmts.push(Stmt::Exit);
+                    }
+                },
+                Opcode::PushZero => stack.push(Expr::Int(0)),
+                Opcode::Mul => binary(&mut stack, BinaryOp::Mul),
+                Opcode::Add => binary(&mut stack, BinaryOp::Add),
+                Opcode::Sub => binary(&mut stack, BinaryOp::Sub),
+                Opcode::Div => binary(&mut stack, BinaryOp::Div),
+                Opcode::Mod => binary(&mut stack, BinaryOp::Mod),
+                Opcode::JoinStr => binary(&mut stack, BinaryOp::JoinStr),
+                Opcode::JoinPadStr => binary(&mut stack, BinaryOp::JoinPadStr),
+                Opcode::Lt => binary(&mut stack, BinaryOp::Lt),
+                Opcode::LtEq => binary(&mut stack, BinaryOp::LtEq),
+                Opcode::NtEq => binary(&mut stack, BinaryOp::NtEq),
+                Opcode::Eq => binary(&mut stack, BinaryOp::Eq),
+                Opcode::Gt => binary(&mut stack, BinaryOp::Gt),
+                Opcode::GtEq => binary(&mut stack, BinaryOp::GtEq),
+                Opcode::And => binary(&mut stack, BinaryOp::And),
+                Opcode::Or => binary(&mut stack, BinaryOp::Or),
+                Opcode::ContainsStr => binary(&mut stack, BinaryOp::ContainsStr),
+                Opcode::Contains0Str => binary(&mut stack, BinaryOp::Contains0Str),
+                Opcode::Inv => {
+                    let value = pop(&mut stack);
+                    stack.push(Expr::Neg(Box::new(value)));
+                },
+                Opcode::Not => {
+                    let value = pop(&mut stack);
+                    stack.push(Expr::Not(Box::new(value)));
+                },
+                Opcode::OntoSpr | Opcode::IntoSpr => {
+                    let second = pop(&mut stack);
+                    let first = Expr::Sprite(Box::new(pop(&mut stack)));
+
+                    let op = match instruction.opcode() {
+                        Opcode::OntoSpr => BinaryOp::Intersects,
+                        _ => BinaryOp::Within,
+                    };
+
+                    stack.push(Expr::Binary(op, Box::new(first), Box::new(second)));
+                },
+                Opcode::GetChunk => {
+                    let string = pop(&mut stack);
+                    let chunk = chunk_ref(&mut stack, string);
+                    stack.push(chunk);
+                },
+                Opcode::HiliteChunk => {
+                    let field = self.field(&mut stack);
+                    let chunk = chunk_ref(&mut stack, field);
+                    stmts.push(Stmt::Hilite(chunk));
+                },
+                Opcode::GetField => {
+                    let field = self.field(&mut stack);
+                    stack.push(field);
+                },
+                Opcode::StartTell => {
+                    let window = pop(&mut stack);
+                    let end_tell = self.end_tell(i, end);
+
+                    let body = self.block(i, end_tell);
+                    stmts.push(Stmt::Tell(window, body));
+
+                    i = (end_tell + 1).min(end);
+                },
+                Opcode::EndTell => {},
+                Opcode::PushList => {
+                    let (args, _) = arg_list(pop(&mut stack));
+                    stack.push(Expr::List(args));
+                },
+                Opcode::PushPropList => {
+                    let (args, _) = arg_list(pop(&mut stack));
+
+                    let mut items = Vec::new();
+                    let mut args = args.into_iter();
+
+                    while let (Some(key), Some(value)) = (args.next(), args.next()) {
+                        items.push((key, value));
+                    }
+
+                    stack.push(Expr::PropList(items));
+                },
+                Opcode::Swap => {
+                    let len = stack.len();
+
+                    if len >= 2 {
+                        stack.swap(len - 1, len - 2);
+                    }
+                },
+                Opcode::PushInt8 | Opcode::PushInt16 | Opcode::PushInt32 => {
+                    stack.push(Expr::Int(operand));
+                },
+                Opcode::PushFloat32 => {
+                    stack.push(Expr::Float(f32::from_bits(operand as u32) as f64));
+                },
+                Opcode::PushArgList | Opcode::PushArgListNoRet => {
+                    let count = usize::try_from(operand).unwrap_or(0).min(stack.len());
+                    let args = stack.split_off(stack.len() - count);
+
+                    let no_ret = instruction.opcode() == Opcode::PushArgListNoRet;
+
+                    stack.push(Expr::ArgList(args, no_ret));
+                },
+                Opcode::PushCons => {
+                    let literal = match instruction.reference() {
+                        Reference::Literal(Literal::String(string)) => Expr::String(string.clone()),
+                        Reference::Literal(Literal::Int(int)) => Expr::Int(*int),
+                        Reference::Literal(Literal::Float(float)) => Expr::Float(*float),
+                        reference => Expr::Unknown(format!("literal {}", reference)),
+                    };
+
+                    stack.push(literal);
+                },
+                Opcode::PushSymb => {
+                    stack.push(Expr::Symbol(name(&instruction).trim_start_matches('#').to_string()));
+                },
+                Opcode::PushVarRef | Opcode::GetGlobal | Opcode::GetGlobal2 | Opcode::GetProp |
+                Opcode::GetParam | Opcode::GetLocal | Opcode::GetTopLevelProp => {
+                    stack.push(Expr::Var(name(&instruction)));
+                },
+                Opcode::SetGlobal | Opcode::SetGlobal2 | Opcode::SetProp |
+                Opcode::SetParam | Opcode::SetLocal => {
+                    let value = pop(&mut stack);
+                    stmts.push(Stmt::Assign(Expr::Var(name(&instruction)), value));
+                },
+                Opcode::Jmp => {
+                    let target = jump_target(&instruction);
+
+                    let stmt = match self.loops.last() {
+                        Some(&(_, loop_end)) if target == loop_end => Stmt::ExitRepeat,
+                        Some(&(loop_start, _)) if target == loop_start => Stmt::NextRepeat,
+                        _ => Stmt::Comment(format!("jump to [{}]", target)),
+                    };
+
+                    stmts.push(stmt);
+                },
+                Opcode::EndRepeat => {
+                    stmts.push(Stmt::Comment(format!("end repeat to [{}]", jump_target(&instruction))));
+                },
+                Opcode::JmpIfZ => {
+                    let condition = pop(&mut stack);
+
+                    i = self.branch(&instruction, condition, i, end, &mut stmts);
+                },
+                Opcode::LocalCall | Opcode::ExtCall | Opcode::TellCall => {
+                    let (args, no_ret) = arg_list(pop(&mut stack));
+                    let name = name(&instruction);
+
+                    if name == "return" && instruction.opcode() == Opcode::ExtCall {
+                        stmts.push(Stmt::Return(args.into_iter().next()));
+                    } else {
+                        push_call(&mut stack, &mut stmts, Expr::Call(name, args), no_ret);
+                    }
+                },
+                Opcode::ObjCall => {
+                    let (args, no_ret) = arg_list(pop(&mut stack));
+
+                    push_call(&mut stack, &mut stmts, Expr::ObjCall(name(&instruction), args), no_ret);
+                },
+                Opcode::NewObj => {
+                    let (args, _) = arg_list(pop(&mut stack));
+
+                    stack.push(Expr::Call(format!("new {}", name(&instruction)), args));
+                },
+                Opcode::Put => {
+                    let put = put_type(operand);
+                    let target = self.variable(&mut stack, operand & 0xf);
+                    let value = pop(&mut stack);
+
+                    stmts.push(Stmt::Put(put, target, value));
+                },
+                Opcode::PutChunk => {
+                    let put = put_type(operand);
+                    let target = self.variable(&mut stack, operand & 0xf);
+                    let chunk = chunk_ref(&mut stack, target);
+                    let value = pop(&mut stack);
+
+                    stmts.push(Stmt::Put(put, chunk, value));
+                },
+                Opcode::DeleteChunk => {
+                    let target = self.variable(&mut stack, operand);
+                    let chunk = chunk_ref(&mut stack, target);
+
+                    stmts.push(Stmt::Delete(chunk));
+                },
+                Opcode::PushChunkVarRef => {
+                    let target = self.variable(&mut stack, operand);
+                    stack.push(target);
+                },
+                Opcode::Get => {
+                    let id = pop(&mut stack);
+                    let property = self.property(&mut stack, operand, id);
+
+                    stack.push(property);
+                },
+                Opcode::Set => {
+                    let id = pop(&mut stack);
+                    let value = pop(&mut stack);
+                    let property = self.property(&mut stack, operand, id);
+
+                    stmts.push(Stmt::Assign(property, value));
+                },
+                Opcode::GetMovieProp => stack.push(Expr::The(name(&instruction))),
+                Opcode::SetMovieProp => {
+                    let value = pop(&mut stack);
+                    stmts.push(Stmt::Assign(Expr::The(name(&instruction)), value));
+                },
+                Opcode::GetObjProp | Opcode::GetChainedProp => {
+                    let object = pop(&mut stack);
+                    stack.push(Expr::Prop(Box::new(object), name(&instruction)));
+                },
+                Opcode::SetObjProp => {
+                    let value = pop(&mut stack);
+                    let object = pop(&mut stack);
+
+                    stmts.push(Stmt::Assign(Expr::Prop(Box::new(object), name(&instruction)), value));
+                },
+                Opcode::TheEntity => {
+                    // The arguments are always empty
+                    pop(&mut stack);
+                    stack.push(Expr::The(name(&instruction)));
+                },
+                Opcode::Peek => {
+                    let value = usize::try_from(operand).ok()
+                        .and_then(|n| stack.len().checked_sub(n + 1))
+                        .map(|index| stack[index].clone())
+                        .unwrap_or_else(|| Expr::Unknown("peek".to_string()));
+
+                    stack.push(value);
+                },
+                Opcode::Pop => {
+                    for _ in 0..operand.max(0) {
+                        pop(&mut stack);
+                    }
+                },
+                opcode @ Opcode::ObjCallV4 | opcode @ Opcode::CallJavaScript |
+                opcode @ Opcode::Unknown(_) => {
+                    stmts.push(Stmt::Comment(format!("unsupported opcode {}", opcode)));
+                },
+            }
+        }
+
+        stmts
+    }
+
+    // Decompile the if statement or repeat loop that starts with the
+    // jmpifz 'instruction', which is followed by the instruction at index
+    // 'next'. Returns the index to continue from.
+    fn branch(
+        &mut self,
+        instruction: &Instruction,
+        condition: Expr,
+        next: usize,
+        end: usize,
+        stmts: &mut Vec<Stmt>
+    ) -> usize {
+        let target = jump_target(instruction);
+        let target_index = self.index_of(target).min(end);
+
+        // A jump to itself or further back isn't an if statement or a loop,
+        // and decompiling it as one would run the same jump again forever
+        if target_index < next {
+            let condition = ast::expr(&condition, Syntax::Verbose);
+
+            stmts.push(Stmt::Comment(format!("jump to [{}] if {} is zero", target, condition)));
+
+            return next;
+        }
+
+        let last = match target_index.checked_sub(1) {
+            Some(last) if last >= next => Some(&self.instructions[last]),
+            _ => None,
+        };
+
+        match last.map(|last| (last.opcode(), jump_target(last))) {
+            // A loop jumps back to before its condition at the end
+            Some((Opcode::EndRepeat, loop_start)) if loop_start <= instruction.offset() => {
+                if let Some(stmt) = self.repeat_with(&condition, next, target_index - 1, target, stmts) {
+                    stmts.push(stmt);
+
+                    return target_index;
+                }
+
+                self.loops.push((loop_start, target));
+                let body = self.block(next, target_index - 1);
+                self.loops.pop();
+
+                stmts.push(Stmt::RepeatWhile(condition, body));
+
+                target_index
+            },
+            // An if statement with an else jumps over the else at the end
+            Some((Opcode::Jmp, else_end)) if else_end > target && !self.is_loop_jump(else_end) => {
+                let else_index = self.index_of(else_end).min(end);
+
+                let then = self.block(next, target_index - 1);
+                let otherwise = self.block(target_index, else_index);
+
+                stmts.push(Stmt::If(condition, then, otherwise));
+
+                else_index
+            },
+            _ => {
+                let then = self.block(next, target_index);
+
+                stmts.push(Stmt::If(condition, then, Vec::new()));
+
+                target_index
+            },
+        }
+    }
+
+    // A repeat with loop compiles to a repeat while loop, which is preceded
+    // by setting the variable and ends with stepping it by one. 'body_end'
+    // is the index of the endrepeat, and 'loop_end' the offset after it.
+    // NOTE "repeat with in" loops over lists aren't reconstructed, and are
+    // written as the repeat while loop they compile to.
+    fn repeat_with(
+        &mut self,
+        condition: &Expr,
+        next: usize,
+        body_end: usize,
+        loop_end: usize,
+        stmts: &mut Vec<Stmt>
+    ) -> Option<Stmt> {
+        let (down, var, end) = match condition {
+            Expr::Binary(BinaryOp::LtEq, var, end) => (false, var, end),
+            Expr::Binary(BinaryOp::GtEq, var, end) => (true, var, end),
+            _ => return None,
+        };
+
+        let var = match var.as_ref() {
+            Expr::Var(var) => var,
+            _ => return None,
+        };
+
+        let start = match stmts.last() {
+            Some(Stmt::Assign(Expr::Var(name), start)) if name == var => start.clone(),
+            _ => return None,
+        };
+
+        let step = body_end.checked_sub(4).filter(|&step| step >= next)?;
+
+        if !is_step(&self.instructions[step..body_end], var, down) {
+            return None;
+        }
+
+        stmts.pop();
+
+        // A next repeat jumps to the step rather than the condition
+        self.loops.push((self.instructions[step].offset(), loop_end));
+        let body = self.block(next, step);
+        self.loops.pop();
+
+        Some(Stmt::RepeatWith(var.clone(), start, end.as_ref().clone(), down, body))
+    }
+
+    // Whether a jump to 'target' is an exit repeat or next repeat.
+    fn is_loop_jump(&self, target: usize) -> bool {
+        self.loops.last().is_some_and(|&(start, end)| target == start || target == end)
+    }
+
+    // The index of the instruction at 'offset', or the number of
+    // instructions if it is past the end.
+    fn index_of(&self, offset: usize) -> usize {
+        self.instructions.iter()
+            .position(|instruction| instruction.offset() >= offset)
+            .unwrap_or(self.instructions.len())
+    }
+
+    // The index of the endtell matching the starttell before 'start'.
+    fn end_tell(&self, start: usize, end: usize) -> usize {
+        let mut depth = 0;
+
+        for index in start..end {
+            match self.instructions[index].opcode() {
+                Opcode::StartTell => depth += 1,
+                Opcode::EndTell if depth == 0 => return index,
+                Opcode::EndTell => depth -= 1,
+                _ => {},
+            }
+        }
+
+        end
+    }
+
+    // A field, with its cast library from Director 5.
+    fn field(&self, stack: &mut Vec<Expr>) -> Expr {
+        let cast = if self.script.version() >= 500 {
+            Some(Box::new(pop(stack)))
+        } else {
+            None
+        };
+
+        let field = pop(stack);
+
+        Expr::Member("field", Box::new(field), cast)
+    }
+
+    // The target of a put, of the type 'kind'.
+    fn variable(&self, stack: &mut Vec<Expr>, kind: i32) -> Expr {
+        if kind == 6 {
+            return self.field(stack);
+        }
+
+        let id = pop(stack);
+
+        let index = |id: &Expr| match id {
+            Expr::Int(id) => usize::try_from(id / disassembler::variable_multiplier(self.script)).ok(),
+            _ => None,
+        };
+
+        let name = |ids: &Vec<i16>, index: Option<usize>| {
+            index.and_then(|index| ids.get(index))
+                .and_then(|&id| self.names.name(id))
+                .map(|name| Expr::Var(name.to_string()))
+        };
+
+        let variable = match (kind, &id) {
+            // Globals and properties
+            (1..=3, Expr::Symbol(name)) => Some(Expr::Var(name.clone())),
+            (1..=3, Expr::Var(name)) => Some(Expr::Var(name.clone())),
+            (1..=3, &Expr::Int(id)) => {
+                i16::try_from(id).ok()
+                    .and_then(|id| self.names.name(id))
+                    .map(|name| Expr::Var(name.to_string()))
+            },
+            (4, id) => name(self.handler.arguments(), index(id)),
+            (5, id) => name(self.handler.locals(), index(id)),
+            _ => None,
+        };
+
+        variable.unwrap_or_else(|| Expr::Unknown(format!("variable type {}", kind)))
+    }
+
+    // A property of "the" that is read or set with get and set. Some
+    // of them are properties of an object, such as a sprite, which
+    // is popped from the stack.
+    fn property(&self, stack: &mut Vec<Expr>, kind: i32, id: Expr) -> Expr {
+        let id = match id {
+            Expr::Int(id) => id,
+            id => return Expr::Unknown(format!("property {}", ast::expr(&id, Syntax::Verbose))),
+        };
+
+        let lookup = |names: &[&str]| -> String {
+            match usize::try_from(id).ok().and_then(|id| names.get(id)) {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ => format!("unknownProperty{}", id),
+            }
+        };
+
+        match kind {
+            0x00 if id <= 0x0b => Expr::The(lookup(&MOVIE_PROPERTIES)),
+            0x00 => {
+                let string = pop(stack);
+
+                match ChunkType::from_id(id - 0x0b) {
+                    Some(chunk) => Expr::LastChunk(chunk, Box::new(string)),
+                    None => Expr::Unknown(format!("last chunk {}", id)),
+                }
+            },
+            0x01 => {
+                let string = pop(stack);
+
+                match ChunkType::from_id(id) {
+                    Some(chunk) => Expr::ChunkCount(chunk, Box::new(string)),
+                    None => Expr::Unknown(format!("chunk count {}", id)),
+                }
+            },
+            0x02 => {
+                let menu = pop(stack);
+                let menu = Expr::Member("menu", Box::new(menu), None);
+
+                Expr::Prop(Box::new(menu), lookup(&MENU_PROPERTIES))
+            },
+            0x03 => {
+                let menu = pop(stack);
+                let item = pop(stack);
+                let item = Expr::MenuItem(Box::new(item), Box::new(menu));
+
+                Expr::Prop(Box::new(item), lookup(&MENU_ITEM_PROPERTIES))
+            },
+            0x04 => {
+                let sound = pop(stack);
+                let sound = Expr::Member("sound", Box::new(sound), None);
+
+                Expr::Prop(Box::new(sound), lookup(&SOUND_PROPERTIES))
+            },
+            0x06 => {
+                let sprite = pop(stack);
+
+                Expr::Prop(Box::new(Expr::Sprite(Box::new(sprite))), lookup(&SPRITE_PROPERTIES))
+            },
+            0x07 => Expr::The(lookup(&ANIMATION_PROPERTIES)),
+            0x08 => {
+                // From Director 5, the number of castMembers is of a cast library
+                if id == 0x02 && self.script.version() >= 500 {
+                    let cast = pop(stack);
+
+                    if cast != Expr::Int(0) {
+                        let cast = Expr::Member("castLib", Box::new(cast), None);
+
+                        return Expr::Prop(Box::new(cast), lookup(&ANIMATION2_PROPERTIES));
+                    }
+                }
+
+                Expr::The(lookup(&ANIMATION2_PROPERTIES))
+            },
+            0x09..=0x15 => {
+                let cast = if self.script.version() >= 500 {
+                    Some(Box::new(pop(stack)))
+                } else {
+                    None
+                };
+
+                let member = pop(stack);
+                let member = Expr::Member("member", Box::new(member), cast);
+
+                Expr::Prop(Box::new(member), lookup(&MEMBER_PROPERTIES))
+            },
+            kind => Expr::Unknown(format!("property type {} id {}", kind, id)),
+        }
+    }
+}
+
+fn pop(stack: &mut Vec<Expr>) -> Expr {
+    stack.pop().unwrap_or_else(|| Expr::Unknown("empty stack".to_string()))
+}
+
+fn binary(stack: &mut Vec<Expr>, op: BinaryOp) {
+    let right = pop(stack);
+    let left = pop(stack);
+
+    stack.push(Expr::Binary(op, Box::new(left), Box::new(right)));
+}
+
+// Calls with a no-return argument list are statements,
+// the others are pushed to be used in an expression.
+fn push_call(stack: &mut Vec<Expr>, stmts: &mut Vec<Stmt>, call: Expr, no_ret: bool) {
+    if no_ret {
+        stmts.push(Stmt::Expr(call));
+    } else {
+        stack.push(call);
+    }
+}
+
+fn arg_list(args: Expr) -> (Vec<Expr>, bool) {
+    match args {
+        Expr::ArgList(args, no_ret) => (args, no_ret),
+        arg => (vec![arg], false),
+    }
+}
+
+// Chunk expressions are stored as the first and last line, item, word and
+// char, with zero for the ones that aren't used, under the string.
+fn chunk_ref(stack: &mut Vec<Expr>, string: Expr) -> Expr {
+    let last_line = pop(stack);
+    let first_line = pop(stack);
+    let last_item = pop(stack);
+    let first_item = pop(stack);
+    let last_word = pop(stack);
+    let first_word = pop(stack);
+    let last_char = pop(stack);
+    let first_char = pop(stack);
+
+    let chunks = [
+        (ChunkType::Line, first_line, last_line),
+        (ChunkType::Item, first_item, last_item),
+        (ChunkType::Word, first_word, last_word),
+        (ChunkType::Char, first_char, last_char),
+    ];
+
+    chunks.iter().fold(string, |string, (kind, first, last)| {
+        if first == &Expr::Int(0) {
+            string
+        } else {
+            Expr::Chunk(*kind, Box::new(first.clone()), Box::new(last.clone()), Box::new(string))
+        }
+    })
+}
+
+fn put_type(operand: i32) -> PutType {
+    match (operand >> 4) & 0xf {
+        2 => PutType::After,
+        3 => PutType::Before,
+        _ => PutType::Into,
+    }
+}
+
+fn name(instruction: &Instruction) -> String {
+    match instruction.reference() {
+        Reference::Name(name) | Reference::Argument(name) |
+        Reference::Local(name) | Reference::Handler(name) => name.clone(),
+        _ => format!("UNKNOWN_{}", instruction.operand().unwrap_or(0)),
+    }
+}
+
+// Whether 'instructions' add one to 'var', or subtract one if 'down' is set.
+fn is_step(instructions: &[Instruction], var: &str, down: bool) -> bool {
+    let is_one = |i: &Instruction| i.opcode() == Opcode::PushInt8 && i.operand() == Some(1);
+
+    let is_get = |i: &Instruction| {
+        matches!(
+            i.opcode(),
+            Opcode::GetGlobal | Opcode::GetGlobal2 | Opcode::GetProp | Opcode::GetParam | Opcode::GetLocal
+        ) && name(i) == var
+    };
+
+    let is_set = |i: &Instruction| {
+        matches!(
+            i.opcode(),
+            Opcode::SetGlobal | Opcode::SetGlobal2 | Opcode::SetProp | Opcode::SetParam | Opcode::SetLocal
+        ) && name(i) == var
+    };
+
+    match instructions {
+        [a, b, op, set] if down => {
+            is_get(a) && is_one(b) && op.opcode() == Opcode::Sub && is_set(set)
+        },
+        [a, b, op, set] => {
+            ((is_one(a) && is_get(b)) || (is_get(a) && is_one(b)))
+                && op.opcode() == Opcode::Add && is_set(set)
+        },
+        _ => false,
+    }
+}
+
+fn jump_target(instruction: &Instruction) -> usize {
+    match instruction.reference() {
+        Reference::Jump(target) => *target,
+        _ => instruction.offset(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(bytecode: &[u8]) -> String {
+        run_syntax(bytecode, Syntax::Verbose)
+    }
+
+    fn run_syntax(bytecode: &[u8], syntax: Syntax) -> String {
+        let names = NameTable::new(&["test", "x", "y", "width"]);
+        let handler = Handler::new(0, vec![1], vec![2], bytecode.to_vec());
+        let script = Script::new(500, false, vec![], vec![]);
+
+        decompile_handler(&handler, &script, &names, syntax).unwrap()
+    }
+
+    #[test]
+    fn if_else() {
+        let source = run(&[
+            0x4b, 0x00, 0x55, 0x08,         // if x then
+            0x41, 0x01, 0x52, 0x00,         //   y = 1
+            0x53, 0x06,                     // else
+            0x41, 0x02, 0x52, 0x00,         //   y = 2
+            0x01,
+        ]);
+
+        assert_eq!(source, "on test x\n  if x then\n    y = 1\n  else\n    y = 2\n  end if\nend\n");
+    }
+
+    #[test]
+    fn else_if() {
+        let source = run(&[
+            0x4b, 0x00, 0x55, 0x08,         // if x then
+            0x41, 0x01, 0x52, 0x00,         //   y = 1
+            0x53, 0x10,                     // else
+            0x4c, 0x00, 0x55, 0x08,         //   if y then
+            0x41, 0x02, 0x52, 0x00,         //     y = 2
+            0x53, 0x06,                     //   else
+            0x41, 0x03, 0x52, 0x00,         //     y = 3
+            0x01,
+        ]);
+
+        assert_eq!(
+            source,
+            "on test x\n  if x then\n    y = 1\n  else if y then\n    y = 2\n  else\n    y = 3\n  end if\nend\n"
+        );
+    }
+
+    #[test]
+    fn repeat_while() {
+        let source = run(&[
+            0x4c, 0x00, 0x41, 0x0a, 0x0c,   // repeat while y < 10
+            0x55, 0x1d,
+            0x4b, 0x00, 0x55, 0x04,         //   if x then
+            0x53, 0x17,                     //     exit repeat
+            0x4c, 0x00, 0x41, 0x05, 0x0f,   //   if y = 5 then
+            0x55, 0x07,
+            0xd3, 0xff, 0xff, 0xff, 0xec,   //     next repeat
+            0x4c, 0x00, 0x41, 0x01, 0x05,   //   y = y + 1
+            0x52, 0x00,
+            0x54, 0x20,                     // end repeat
+            0x01,
+        ]);
+
+        assert_eq!(source, [
+            "on test x",
+            "  repeat while y < 10",
+            "    if x then",
+            "      exit repeat",
+            "    end if",
+            "    if y = 5 then",
+            "      next repeat",
+            "    end if",
+            "    y = y + 1",
+            "  end repeat",
+            "end",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn repeat_with() {
+        let source = run(&[
+            0x41, 0x01, 0x52, 0x00,         // repeat with y = 1 to 10
+            0x4c, 0x00, 0x41, 0x0a, 0x0d,
+            0x55, 0x18,
+            0x4c, 0x00, 0x41, 0x05, 0x0f,   //   if y = 5 then
+            0x55, 0x04,
+            0x53, 0x06,                     //     next repeat
+            0x4c, 0x00, 0x51, 0x00,         //   x = y
+            0x41, 0x01, 0x4c, 0x00, 0x05,   // end repeat
+            0x52, 0x00,
+            0x54, 0x1b,
+            0x01,
+        ]);
+
+        assert_eq!(source, [
+            "on test x",
+            "  repeat with y = 1 to 10",
+            "    if y = 5 then",
+            "      next repeat",
+            "    end if",
+            "    x = y",
+            "  end repeat",
+            "end",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn repeat_with_down_to() {
+        let source = run(&[
+            0x41, 0x0a, 0x52, 0x00,         // repeat with y = 10 down to x
+            0x4c, 0x00, 0x4b, 0x00, 0x11,
+            0x55, 0x0f,
+            0x4c, 0x00, 0x51, 0x00,         //   x = y
+            0x4c, 0x00, 0x41, 0x01, 0x06,   // end repeat
+            0x52, 0x00,
+            0x54, 0x12,
+            0x01,
+        ]);
+
+        assert_eq!(source, "on test x\n  repeat with y = 10 down to x\n    x = y\n  end repeat\nend\n");
+    }
+
+    #[test]
+    fn dot_syntax() {
+        let bytecode = [
+            0x41, 0x03, 0x41, 0x0d, 0x5c, 0x06, 0x52, 0x00,         // y = the locH of sprite 3
+            0x41, 0x03, 0x41, 0x0a, 0x41, 0x0d, 0x5d, 0x06,         // set the locH of sprite 3 to 10
+            0x41, 0x01, 0x41, 0x02, 0x41, 0x01, 0x5c, 0x09, 0x52, 0x00, // y = the name of member 1 of castLib 2
+            0x4b, 0x00, 0x61, 0x03, 0x52, 0x00,                     // y = the width of x
+            0x4b, 0x00, 0x41, 0x05, 0x62, 0x03,                     // set the width of x to 5
+            0x01,
+        ];
+
+        assert_eq!(run_syntax(&bytecode, Syntax::Verbose), [
+            "on test x",
+            "  y = the locH of sprite 3",
+            "  set the locH of sprite 3 to 10",
+            "  y = the name of member 1 of castLib 2",
+            "  y = the width of x",
+            "  set the width of x to 5",
+            "end",
+            "",
+        ].join("\n"));
+
+        assert_eq!(run_syntax(&bytecode, Syntax::Dot), [
+            "on test x",
+            "  y = sprite(3).locH",
+            "  sprite(3).locH = 10",
+            "  y = member(1, 2).name",
+            "  y = x.width",
+            "  x.width = 5",
+            "end",
+            "",
+        ].join("\n"));
+    }
+
+    #[test]
+    fn backward_jmpifz() {
+        let names = NameTable::new(&["test", "x"]);
+        let script = Script::new(500, false, vec![], vec![]);
+
+        // Jumps to itself, and back to the start
+        for bytecode in [vec![0x4b, 0x00, 0x55, 0x00, 0x01], vec![0x4b, 0x00, 0xd5, 0xff, 0xff, 0xff, 0xfe, 0x01]] {
+            let handler = Handler::new(0, vec![1], vec![], bytecode);
+
+            let stmts = decompile(&handler, &script, &names).unwrap();
+
+            assert!(matches!(stmts.as_slice(), [Stmt::Comment(_)]));
+        }
+    }
+}
@@ -0,0 +1,339 @@
+use std::convert::TryFrom;
+use std::fmt;
+
+use crate::DirpError;
+
+use crate::chunk::lscr::{Handler, Literal, Script};
+use crate::chunk::lnam::NameTable;
+
+use super::opcode::Opcode;
+
+// A single decoded instruction of a handler.
+#[derive(Clone, Debug)]
+pub struct Instruction {
+    offset: usize,
+    byte: u8,
+    opcode: Opcode,
+    operand: Option<i32>,
+    reference: Reference,
+}
+
+impl Instruction {
+    // The offset of the instruction in the bytecode of the handler.
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    // The opcode byte as stored, which also gives the size of the operand.
+    pub fn byte(&self) -> u8 {
+        self.byte
+    }
+
+    pub fn opcode(&self) -> Opcode {
+        self.opcode
+    }
+
+    pub fn operand(&self) -> Option<i32> {
+        self.operand
+    }
+
+    // The number of bytes of the operand.
+    pub fn width(&self) -> usize {
+        operand_width(self.byte)
+    }
+
+    // What the operand refers to, resolved against the script.
+    pub fn reference(&self) -> &Reference {
+        &self.reference
+    }
+}
+
+// What the operand of an instruction refers to. Names that can't
+// be resolved are given as "UNKNOWN_<ID>".
+#[derive(Clone, PartialEq, Debug)]
+pub enum Reference {
+    None,
+    // A name from the name table, such as a symbol,
+    // global, property or external handler.
+    Name(String),
+    // A literal of the script.
+    Literal(Literal),
+    // An argument of the handler.
+    Argument(String),
+    // A local variable of the handler.
+    Local(String),
+    // A handler of the same script.
+    Handler(String),
+    // The offset of the instruction that a jump goes to.
+    Jump(usize),
+    // The value of a pushfloat32.
+    Float(f32),
+}
+
+impl fmt::Display for Reference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reference::None => Ok(()),
+            Reference::Name(name) => write!(f, "{}", name),
+            Reference::Literal(Literal::String(string)) => write!(f, "{:?}", string),
+            Reference::Literal(Literal::Int(int)) => write!(f, "{}", int),
+            Reference::Literal(Literal::Float(float)) => write!(f, "{}", float),
+            Reference::Literal(Literal::Unknown(kind, _)) => write!(f, "<literal type {}>", kind),
+            Reference::Argument(name) => write!(f, "{}", name),
+            Reference::Local(name) => write!(f, "{}", name),
+            Reference::Handler(name) => write!(f, "{}", name),
+            Reference::Jump(offset) => write!(f, "[{}]", offset),
+            Reference::Float(float) => write!(f, "{}", float),
+        }
+    }
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "[{:4}] {}", self.offset, self.opcode)?;
+
+        if let Some(operand) = self.operand {
+            write!(f, " {}", operand)?;
+        }
+
+        if self.reference != Reference::None {
+            write!(f, " ; {}", self.reference)?;
+        }
+
+        Ok(())
+    }
+}
+
+// Decode the bytecode of 'handler', which is a handler of 'script',
+// resolving its operands with the name table 'names'.
+pub fn disassemble(
+    handler: &Handler,
+    script: &Script,
+    names: &NameTable
+) -> Result<Vec<Instruction>, DirpError> {
+    let bytecode = handler.bytecode();
+    let mut instructions = Vec::new();
+    let mut pos = 0;
+
+    while pos < bytecode.len() {
+        let offset = pos;
+        let byte = bytecode[pos];
+        let opcode = Opcode::from_byte(byte);
+        pos += 1;
+
+        let width = operand_width(byte);
+
+        let operand = if width > 0 {
+            let bytes = bytecode.get(pos..pos + width).ok_or(DirpError::Truncated)?;
+            pos += width;
+
+            Some(read_operand(opcode, bytes))
+        } else {
+            None
+        };
+
+        let reference = match operand {
+            Some(operand) => resolve(opcode, operand, offset, handler, script, names),
+            None => Reference::None,
+        };
+
+        instructions.push(Instruction {
+            offset,
+            byte,
+            opcode,
+            operand,
+            reference,
+        });
+    }
+
+    Ok(instructions)
+}
+
+// Opcodes below 0x40 don't have an operand.
+fn operand_width(byte: u8) -> usize {
+    match byte {
+        0xc0..=0xff => 4,
+        0x80..=0xbf => 2,
+        0x40..=0x7f => 1,
+        _ => 0,
+    }
+}
+
+// Operands are big endian. Only the operands of the push opcodes
+// for integers are signed, unless they are four bytes.
+fn read_operand(opcode: Opcode, bytes: &[u8]) -> i32 {
+    // NOTE As in ProjectorRays, a single byte is only signed for PushInt8.
+    let signed = matches!(opcode, Opcode::PushInt8 | Opcode::PushInt16);
+
+    match *bytes {
+        [a] if opcode == Opcode::PushInt8 => a as i8 as i32,
+        [a] => a as i32,
+        [a, b] if signed => i16::from_be_bytes([a, b]) as i32,
+        [a, b] => u16::from_be_bytes([a, b]) as i32,
+        [a, b, c, d] => i32::from_be_bytes([a, b, c, d]),
+        _ => 0,
+    }
+}
+
+// Arguments, locals and literals are referred to by their index in scripts
+// of an LctX script context, and otherwise by their byte offset in tables
+// with entries of a size that depends on the Director version.
+pub(crate) fn variable_multiplier(script: &Script) -> i32 {
+    if script.capital_x() {
+        1
+    } else if script.version() >= 500 {
+        8
+    } else {
+        6
+    }
+}
+
+fn resolve(
+    opcode: Opcode,
+    operand: i32,
+    offset: usize,
+    handler: &Handler,
+    script: &Script,
+    names: &NameTable
+) -> Reference {
+    let name = |id: i32| -> String {
+        i16::try_from(id).ok()
+            .and_then(|id| names.name(id))
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("UNKNOWN_{}", id))
+    };
+
+    let index = operand / variable_multiplier(script);
+
+    let variable = |ids: &Vec<i16>| -> String {
+        usize::try_from(index).ok()
+            .and_then(|index| ids.get(index))
+            .map(|&id| name(id as i32))
+            .unwrap_or_else(|| format!("UNKNOWN_{}", index))
+    };
+
+    match opcode {
+        Opcode::PushSymb => Reference::Name(format!("#{}", name(operand))),
+        Opcode::GetGlobal | Opcode::GetGlobal2 | Opcode::SetGlobal | Opcode::SetGlobal2 |
+        Opcode::GetProp | Opcode::SetProp | Opcode::GetMovieProp | Opcode::SetMovieProp |
+        Opcode::GetObjProp | Opcode::SetObjProp | Opcode::GetChainedProp |
+        Opcode::GetTopLevelProp | Opcode::ExtCall | Opcode::ObjCall | Opcode::TellCall |
+        Opcode::PushVarRef | Opcode::NewObj => Reference::Name(name(operand)),
+        Opcode::PushCons => {
+            match usize::try_from(index).ok().and_then(|index| script.literals().get(index)) {
+                Some(literal) => Reference::Literal(literal.clone()),
+                None => Reference::Name(format!("UNKNOWN_{}", index)),
+            }
+        },
+        Opcode::GetParam | Opcode::SetParam => Reference::Argument(variable(handler.arguments())),
+        Opcode::GetLocal | Opcode::SetLocal => Reference::Local(variable(handler.locals())),
+        Opcode::LocalCall => {
+            let handler = usize::try_from(operand).ok()
+                .and_then(|index| script.handlers().get(index));
+
+            match handler {
+                Some(handler) => Reference::Handler(name(handler.name_id() as i32)),
+                None => Reference::Handler(format!("UNKNOWN_{}", operand)),
+            }
+        },
+        Opcode::Jmp | Opcode::JmpIfZ => {
+            Reference::Jump((offset as i64 + operand as i64).max(0) as usize)
+        },
+        Opcode::EndRepeat => {
+            Reference::Jump((offset as i64 - operand as i64).max(0) as usize)
+        },
+        Opcode::PushFloat32 => Reference::Float(f32::from_bits(operand as u32)),
+        _ => Reference::None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(version: u32, bytecode: &[u8]) -> Vec<Instruction> {
+        run_script(&Script::new(version, false, vec![], vec![]), bytecode)
+    }
+
+    fn run_script(script: &Script, bytecode: &[u8]) -> Vec<Instruction> {
+        let names = NameTable::new(&["test", "a", "b"]);
+        let handler = Handler::new(0, vec![], vec![1, 2], bytecode.to_vec());
+
+        disassemble(&handler, script, &names).unwrap()
+    }
+
+    #[test]
+    fn operand_widths() {
+        let instructions = run(500, &[0x41, 0x05, 0x81, 0x01, 0x00, 0xc1, 0x00, 0x01, 0x00, 0x00, 0x01]);
+
+        let summary = instructions.iter()
+            .map(|i| (i.offset(), i.opcode(), i.width(), i.operand()))
+            .collect::<Vec<_>>();
+
+        assert_eq!(summary, [
+            (0, Opcode::PushInt8, 1, Some(5)),
+            (2, Opcode::PushInt8, 2, Some(0x100)),
+            (5, Opcode::PushInt8, 4, Some(0x10000)),
+            (10, Opcode::Ret, 0, None),
+        ]);
+    }
+
+    #[test]
+    fn signed_operands() {
+        let operands = run(500, &[
+            0x41, 0xff, 0x6e, 0xff, 0xae, 0xff, 0xfe, 0x44, 0xff, 0xef, 0xff, 0xff, 0xff, 0xfd
+        ])
+            .iter()
+            .map(|i| i.operand())
+            .collect::<Vec<_>>();
+
+        // Only the integer pushes are signed below four bytes,
+        // and PushInt16 only with two bytes
+        assert_eq!(operands, [Some(-1), Some(0xff), Some(-2), Some(0xff), Some(-3)]);
+    }
+
+    #[test]
+    fn jump_targets() {
+        let instructions = run(500, &[0x53, 0x04, 0x93, 0x01, 0x00, 0x03, 0xd3, 0xff, 0xff, 0xff, 0xfa]);
+
+        assert_eq!(instructions[0].reference(), &Reference::Jump(4));
+        assert_eq!(instructions[1].reference(), &Reference::Jump(258));
+        assert_eq!(instructions[3].reference(), &Reference::Jump(0));
+    }
+
+    #[test]
+    fn end_repeat_jumps_back() {
+        let instructions = run(500, &[0x41, 0x01, 0x54, 0x02]);
+
+        assert_eq!(instructions[1].reference(), &Reference::Jump(0));
+    }
+
+    #[test]
+    fn variable_multiplier_by_context() {
+        let lctx_404 = Script::new(404, false, vec![], vec![]);
+        let lctx_500 = Script::new(500, false, vec![], vec![]);
+        let lctx_850 = Script::new(850, false, vec![], vec![]);
+        let capital_x = Script::new(500, true, vec![], vec![]);
+
+        assert_eq!(variable_multiplier(&lctx_404), 6);
+        assert_eq!(variable_multiplier(&lctx_500), 8);
+        assert_eq!(variable_multiplier(&lctx_850), 8);
+        assert_eq!(variable_multiplier(&capital_x), 1);
+
+        let local = |script, operand| run_script(script, &[0x4c, operand])[0].reference().clone();
+
+        assert_eq!(local(&lctx_404, 6), Reference::Local("b".to_string()));
+        assert_eq!(local(&lctx_500, 8), Reference::Local("b".to_string()));
+        assert_eq!(local(&capital_x, 1), Reference::Local("b".to_string()));
+        assert_eq!(local(&lctx_500, 1), Reference::Local("a".to_string()));
+    }
+
+    #[test]
+    fn truncated_operand() {
+        let names = NameTable::new(&[]);
+        let handler = Handler::new(0, vec![], vec![], vec![0x81, 0x01]);
+        let script = Script::new(500, false, vec![], vec![]);
+
+        assert!(matches!(disassemble(&handler, &script, &names), Err(DirpError::Truncated)));
+    }
+}
@@ -0,0 +1,272 @@
+use std::fmt;
+
+// The opcodes of Lingo bytecode. Opcodes from 0x40 and up take an operand,
+// which is one byte for 0x40-0x7f, two bytes for 0x80-0xbf and four bytes
+// for 0xc0-0xff. All three forms are the same opcode.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Opcode {
+    // Opcodes without an operand
+    Ret,
+    RetFactory,
+    PushZero,
+    Mul,
+    Add,
+    Sub,
+    Div,
+    Mod,
+    Inv,
+    JoinStr,
+    JoinPadStr,
+    Lt,
+    LtEq,
+    NtEq,
+    Eq,
+    Gt,
+    GtEq,
+    And,
+    Or,
+    Not,
+    ContainsStr,
+    Contains0Str,
+    GetChunk,
+    HiliteChunk,
+    OntoSpr,
+    IntoSpr,
+    GetField,
+    StartTell,
+    EndTell,
+    PushList,
+    PushPropList,
+    Swap,
+    CallJavaScript,
+
+    // Opcodes with an operand
+    PushInt8,
+    PushArgListNoRet,
+    PushArgList,
+    PushCons,
+    PushSymb,
+    PushVarRef,
+    GetGlobal2,
+    GetGlobal,
+    GetProp,
+    GetParam,
+    GetLocal,
+    SetGlobal2,
+    SetGlobal,
+    SetProp,
+    SetParam,
+    SetLocal,
+    Jmp,
+    EndRepeat,
+    JmpIfZ,
+    LocalCall,
+    ExtCall,
+    ObjCallV4,
+    Put,
+    PutChunk,
+    DeleteChunk,
+    Get,
+    Set,
+    GetMovieProp,
+    SetMovieProp,
+    GetObjProp,
+    SetObjProp,
+    TellCall,
+    Peek,
+    Pop,
+    TheEntity,
+    ObjCall,
+    PushChunkVarRef,
+    PushInt16,
+    PushInt32,
+    GetChainedProp,
+    PushFloat32,
+    GetTopLevelProp,
+    NewObj,
+
+    Unknown(u8),
+}
+
+impl Opcode {
+    // Returns the opcode of the byte 'op', no matter the size of its operand.
+    pub fn from_byte(op: u8) -> Opcode {
+        let op = if op >= 0x40 { 0x40 + op % 0x40 } else { op };
+
+        match op {
+            0x01 => Opcode::Ret,
+            0x02 => Opcode::RetFactory,
+            0x03 => Opcode::PushZero,
+            0x04 => Opcode::Mul,
+            0x05 => Opcode::Add,
+            0x06 => Opcode::Sub,
+            0x07 => Opcode::Div,
+            0x08 => Opcode::Mod,
+            0x09 => Opcode::Inv,
+            0x0a => Opcode::JoinStr,
+            0x0b => Opcode::JoinPadStr,
+            0x0c => Opcode::Lt,
+            0x0d => Opcode::LtEq,
+            0x0e => Opcode::NtEq,
+            0x0f => Opcode::Eq,
+            0x10 => Opcode::Gt,
+            0x11 => Opcode::GtEq,
+            0x12 => Opcode::And,
+            0x13 => Opcode::Or,
+            0x14 => Opcode::Not,
+            0x15 => Opcode::ContainsStr,
+            0x16 => Opcode::Contains0Str,
+            0x17 => Opcode::GetChunk,
+            0x18 => Opcode::HiliteChunk,
+            0x19 => Opcode::OntoSpr,
+            0x1a => Opcode::IntoSpr,
+            0x1b => Opcode::GetField,
+            0x1c => Opcode::StartTell,
+            0x1d => Opcode::EndTell,
+            0x1e => Opcode::PushList,
+            0x1f => Opcode::PushPropList,
+            0x21 => Opcode::Swap,
+            0x26 => Opcode::CallJavaScript,
+
+            0x41 => Opcode::PushInt8,
+            0x42 => Opcode::PushArgListNoRet,
+            0x43 => Opcode::PushArgList,
+            0x44 => Opcode::PushCons,
+            0x45 => Opcode::PushSymb,
+            0x46 => Opcode::PushVarRef,
+            0x48 => Opcode::GetGlobal2,
+            0x49 => Opcode::GetGlobal,
+            0x4a => Opcode::GetProp,
+            0x4b => Opcode::GetParam,
+            0x4c => Opcode::GetLocal,
+            0x4e => Opcode::SetGlobal2,
+            0x4f => Opcode::SetGlobal,
+            0x50 => Opcode::SetProp,
+            0x51 => Opcode::SetParam,
+            0x52 => Opcode::SetLocal,
+            0x53 => Opcode::Jmp,
+            0x54 => Opcode::EndRepeat,
+            0x55 => Opcode::JmpIfZ,
+            0x56 => Opcode::LocalCall,
+            0x57 => Opcode::ExtCall,
+            0x58 => Opcode::ObjCallV4,
+            0x59 => Opcode::Put,
+            0x5a => Opcode::PutChunk,
+            0x5b => Opcode::DeleteChunk,
+            0x5c => Opcode::Get,
+            0x5d => Opcode::Set,
+            0x5f => Opcode::GetMovieProp,
+            0x60 => Opcode::SetMovieProp,
+            0x61 => Opcode::GetObjProp,
+            0x62 => Opcode::SetObjProp,
+            0x63 => Opcode::TellCall,
+            0x64 => Opcode::Peek,
+            0x65 => Opcode::Pop,
+            0x66 => Opcode::TheEntity,
+            0x67 => Opcode::ObjCall,
+            0x6d => Opcode::PushChunkVarRef,
+            0x6e => Opcode::PushInt16,
+            0x6f => Opcode::PushInt32,
+            0x70 => Opcode::GetChainedProp,
+            0x71 => Opcode::PushFloat32,
+            0x72 => Opcode::GetTopLevelProp,
+            0x73 => Opcode::NewObj,
+
+            op => Opcode::Unknown(op),
+        }
+    }
+
+    // The name of the opcode, as used in disassembly listings.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Opcode::Ret => "ret",
+            Opcode::RetFactory => "retfactory",
+            Opcode::PushZero => "pushzero",
+            Opcode::Mul => "mul",
+            Opcode::Add => "add",
+            Opcode::Sub => "sub",
+            Opcode::Div => "div",
+            Opcode::Mod => "mod",
+            Opcode::Inv => "inv",
+            Opcode::JoinStr => "joinstr",
+            Opcode::JoinPadStr => "joinpadstr",
+            Opcode::Lt => "lt",
+            Opcode::LtEq => "lteq",
+            Opcode::NtEq => "nteq",
+            Opcode::Eq => "eq",
+            Opcode::Gt => "gt",
+            Opcode::GtEq => "gteq",
+            Opcode::And => "and",
+            Opcode::Or => "or",
+            Opcode::Not => "not",
+            Opcode::ContainsStr => "containsstr",
+            Opcode::Contains0Str => "contains0str",
+            Opcode::GetChunk => "getchunk",
+            Opcode::HiliteChunk => "hilitechunk",
+            Opcode::OntoSpr => "ontospr",
+            Opcode::IntoSpr => "intospr",
+            Opcode::GetField => "getfield",
+            Opcode::StartTell => "starttell",
+            Opcode::EndTell => "endtell",
+            Opcode::PushList => "pushlist",
+            Opcode::PushPropList => "pushproplist",
+            Opcode::Swap => "swap",
+            Opcode::CallJavaScript => "calljavascript",
+
+            Opcode::PushInt8 => "pushint8",
+            Opcode::PushArgListNoRet => "pusharglistnoret",
+            Opcode::PushArgList => "pusharglist",
+            Opcode::PushCons => "pushcons",
+            Opcode::PushSymb => "pushsymb",
+            Opcode::PushVarRef => "pushvarref",
+            Opcode::GetGlobal2 => "getglobal2",
+            Opcode::GetGlobal => "getglobal",
+            Opcode::GetProp => "getprop",
+            Opcode::GetParam => "getparam",
+            Opcode::GetLocal => "getlocal",
+            Opcode::SetGlobal2 => "setglobal2",
+            Opcode::SetGlobal => "setglobal",
+            Opcode::SetProp => "setprop",
+            Opcode::SetParam => "setparam",
+            Opcode::SetLocal => "setlocal",
+            Opcode::Jmp => "jmp",
+            Opcode::EndRepeat => "endrepeat",
+            Opcode::JmpIfZ => "jmpifz",
+            Opcode::LocalCall => "localcall",
+            Opcode::ExtCall => "extcall",
+            Opcode::ObjCallV4 => "objcallv4",
+            Opcode::Put => "put",
+            Opcode::PutChunk => "putchunk",
+            Opcode::DeleteChunk => "deletechunk",
+            Opcode::Get => "get",
+            Opcode::Set => "set",
+            Opcode::GetMovieProp => "getmovieprop",
+            Opcode::SetMovieProp => "setmovieprop",
+            Opcode::GetObjProp => "getobjprop",
+            Opcode::SetObjProp => "setobjprop",
+            Opcode::TellCall => "tellcall",
+            Opcode::Peek => "peek",
+            Opcode::Pop => "pop",
+            Opcode::TheEntity => "theentity",
+            Opcode::ObjCall => "objcall",
+            Opcode::PushChunkVarRef => "pushchunkvarref",
+            Opcode::PushInt16 => "pushint16",
+            Opcode::PushInt32 => "pushint32",
+            Opcode::GetChainedProp => "getchainedprop",
+            Opcode::PushFloat32 => "pushfloat32",
+            Opcode::GetTopLevelProp => "gettoplevelprop",
+            Opcode::NewObj => "newobj",
+
+            Opcode::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for Opcode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Opcode::Unknown(op) => write!(f, "unk_{:02x}", op),
+            opcode => write!(f, "{}", opcode.name()),
+        }
+    }
+}
@@ -0,0 +1,220 @@
+// The palettes that are built into Director. Bitmaps refer to these with
+// a negative (or zero) palette ID instead of the number of a palette
+// cast member.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum BuiltinPalette {
+    SystemMac,
+    Rainbow,
+    Grayscale,
+    Pastels,
+    Vivid,
+    Ntsc,
+    Metallic,
+    Web216,
+    Vga,
+    SystemWinDir4,
+    SystemWin,
+}
+
+// The levels of the 6x6x6 color cube, which is the base of
+// the System Mac and Web 216 palettes.
+const CUBE: [u8; 6] = [0xff, 0xcc, 0x99, 0x66, 0x33, 0x00];
+
+// The levels of the red, green, blue and gray ramps in the System Mac palette.
+const RAMP: [u8; 10] = [0xee, 0xdd, 0xbb, 0xaa, 0x88, 0x77, 0x55, 0x44, 0x22, 0x11];
+
+// The 20 colors that Windows reserves in every 256 color palette.
+const WINDOWS_STATIC: [[u8; 3]; 20] = [
+    [0x00, 0x00, 0x00], [0x80, 0x00, 0x00], [0x00, 0x80, 0x00], [0x80, 0x80, 0x00],
+    [0x00, 0x00, 0x80], [0x80, 0x00, 0x80], [0x00, 0x80, 0x80], [0xc0, 0xc0, 0xc0],
+    [0xc0, 0xdc, 0xc0], [0xa6, 0xca, 0xf0], [0xff, 0xfb, 0xf0], [0xa0, 0xa0, 0xa4],
+    [0x80, 0x80, 0x80], [0xff, 0x00, 0x00], [0x00, 0xff, 0x00], [0xff, 0xff, 0x00],
+    [0x00, 0x00, 0xff], [0xff, 0x00, 0xff], [0x00, 0xff, 0xff], [0xff, 0xff, 0xff],
+];
+
+impl BuiltinPalette {
+    // Returns the built-in palette for a palette ID stored in a bitmap.
+    pub fn from_id(id: i16) -> Option<BuiltinPalette> {
+        match id {
+            0 => Some(BuiltinPalette::SystemMac),
+            -1 => Some(BuiltinPalette::Rainbow),
+            -2 => Some(BuiltinPalette::Grayscale),
+            -3 => Some(BuiltinPalette::Pastels),
+            -4 => Some(BuiltinPalette::Vivid),
+            -5 => Some(BuiltinPalette::Ntsc),
+            -6 => Some(BuiltinPalette::Metallic),
+            -7 => Some(BuiltinPalette::Web216),
+            -8 => Some(BuiltinPalette::Vga),
+            -100 => Some(BuiltinPalette::SystemWinDir4),
+            -101 => Some(BuiltinPalette::SystemWin),
+            _ => None,
+        }
+    }
+
+    // The 8-bit red, green and blue values of the colors in the palette.
+    //
+    // NOTE Only the System Mac and Grayscale palettes are known to be exact.
+    // The others are generated to resemble the palettes of Director,
+    // and may not match them color for color.
+    pub fn colors(self) -> Vec<[u8; 3]> {
+        match self {
+            BuiltinPalette::SystemMac => system_mac(),
+            BuiltinPalette::Rainbow => rainbow(),
+            BuiltinPalette::Grayscale => grayscale(),
+            BuiltinPalette::Pastels => {
+                // The rainbow, mixed halfway with white
+                map(rainbow(), |c| 0x80 + c / 2)
+            },
+            BuiltinPalette::Vivid => {
+                // The rainbow, with every channel pushed to its extremes
+                map(rainbow(), |c| if c >= 0x80 { 0xff } else { c / 2 })
+            },
+            BuiltinPalette::Ntsc => {
+                // The System Mac palette, limited to the NTSC safe range
+                map(system_mac(), |c| 16 + (c as u16 * 219 / 255) as u8)
+            },
+            BuiltinPalette::Metallic => metallic(),
+            BuiltinPalette::Web216 => web216(),
+            BuiltinPalette::Vga => vga(),
+            BuiltinPalette::SystemWinDir4 | BuiltinPalette::SystemWin => system_win(),
+        }
+    }
+}
+
+// The 6x6x6 color cube from white to black, followed by ramps
+// of red, green, blue and gray, and black.
+fn system_mac() -> Vec<[u8; 3]> {
+    let mut colors = cube();
+
+    // The cube ends with black, which is moved to the end of the palette.
+    colors.pop();
+
+    for channel in 0..3 {
+        for &level in RAMP.iter() {
+            let mut color = [0; 3];
+            color[channel] = level;
+            colors.push(color);
+        }
+    }
+
+    for &level in RAMP.iter() {
+        colors.push([level, level, level]);
+    }
+
+    colors.push([0, 0, 0]);
+
+    colors
+}
+
+// The 6x6x6 color cube from white to black, padded with black.
+fn web216() -> Vec<[u8; 3]> {
+    let mut colors = cube();
+    colors.resize(256, [0, 0, 0]);
+    colors
+}
+
+fn cube() -> Vec<[u8; 3]> {
+    let mut colors = Vec::with_capacity(216);
+
+    for &r in CUBE.iter() {
+        for &g in CUBE.iter() {
+            for &b in CUBE.iter() {
+                colors.push([r, g, b]);
+            }
+        }
+    }
+
+    colors
+}
+
+fn grayscale() -> Vec<[u8; 3]> {
+    (0..=255u8).rev().map(|v| [v, v, v]).collect()
+}
+
+// White, the hues of the color wheel, and black.
+fn rainbow() -> Vec<[u8; 3]> {
+    let mut colors = vec![[0xff, 0xff, 0xff]];
+
+    for i in 0..254 {
+        colors.push(hue(i as f32 / 254.0));
+    }
+
+    colors.push([0, 0, 0]);
+
+    colors
+}
+
+// Ramps of gold, silver and copper tones.
+fn metallic() -> Vec<[u8; 3]> {
+    let tones: [[f32; 3]; 3] = [[1.0, 0.84, 0.0], [0.9, 0.9, 0.95], [0.85, 0.5, 0.2]];
+
+    let mut colors = vec![[0xff, 0xff, 0xff]];
+
+    for i in 0..254 {
+        let tone = tones[i % 3];
+        let level = 1.0 - (i / 3) as f32 / 85.0;
+
+        colors.push([
+            (tone[0] * level * 255.0) as u8,
+            (tone[1] * level * 255.0) as u8,
+            (tone[2] * level * 255.0) as u8,
+        ]);
+    }
+
+    colors.push([0, 0, 0]);
+
+    colors
+}
+
+// The 16 colors of VGA, from white to black.
+fn vga() -> Vec<[u8; 3]> {
+    let mut colors: Vec<[u8; 3]> = WINDOWS_STATIC[..8].iter()
+        .chain(WINDOWS_STATIC[12..].iter())
+        .copied()
+        .collect();
+
+    colors.sort_by_key(|&[r, g, b]| std::cmp::Reverse(r as u16 + g as u16 + b as u16));
+
+    colors
+}
+
+// The static Windows colors around a 6x6x6 color cube and a gray ramp.
+// Director orders its palettes from white to black, so this starts with
+// the last of the static colors.
+fn system_win() -> Vec<[u8; 3]> {
+    let mut colors: Vec<[u8; 3]> = WINDOWS_STATIC[10..].iter().rev().copied().collect();
+
+    colors.extend(cube());
+
+    while colors.len() < 246 {
+        let v = (255 - (colors.len() - 226) * 255 / 20) as u8;
+        colors.push([v, v, v]);
+    }
+
+    colors.extend(WINDOWS_STATIC[..10].iter().rev());
+
+    colors
+}
+
+// A fully saturated color with the hue 'h', from 0 to 1.
+fn hue(h: f32) -> [u8; 3] {
+    let h = h * 6.0;
+    let x = 1.0 - (h % 2.0 - 1.0).abs();
+
+    let (r, g, b) = match h as u32 {
+        0 => (1.0, x, 0.0),
+        1 => (x, 1.0, 0.0),
+        2 => (0.0, 1.0, x),
+        3 => (0.0, x, 1.0),
+        4 => (x, 0.0, 1.0),
+        _ => (1.0, 0.0, x),
+    };
+
+    [(r * 255.0) as u8, (g * 255.0) as u8, (b * 255.0) as u8]
+}
+
+fn map<F: Fn(u8) -> u8>(colors: Vec<[u8; 3]>, f: F) -> Vec<[u8; 3]> {
+    colors.into_iter()
+        .map(|[r, g, b]| [f(r), f(g), f(b)])
+        .collect()
+}
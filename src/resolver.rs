@@ -0,0 +1,245 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::{DirectorFile, DirpError};
+
+use crate::chunk::mcsl::Cast;
+
+// Loads the external casts of a movie. 'load' returns None if the cast can't
+// be found, and otherwise the cast read with DirectorFile::base or
+// DirectorFile::base_from_reader.
+pub trait CastLoader {
+    fn load(&self, cast: &Cast) -> Option<Result<DirectorFile, DirpError>>;
+}
+
+impl<F> CastLoader for F
+    where F: Fn(&Cast) -> Option<Result<DirectorFile, DirpError>>
+{
+    fn load(&self, cast: &Cast) -> Option<Result<DirectorFile, DirpError>> {
+        self(cast)
+    }
+}
+
+// The extensions of cast files, in the order they are tried.
+const EXTENSIONS: [&str; 3] = ["cst", "cxt", "cct"];
+
+type Callback = Box<dyn Fn(&Cast) -> Option<PathBuf>>;
+
+// Finds the files of external casts. The paths stored in a movie are the
+// paths on the machine that made it, like "HD:Folder:Cast.cst" on a Mac or
+// "C:\Folder\Cast.cxt" on Windows, so only the file name is used. The file
+// is looked for in the directory of the movie and then in the search paths,
+// with any of the cast file extensions and ignoring case.
+#[derive(Default)]
+pub struct CastResolver {
+    search_paths: Vec<PathBuf>,
+    callback: Option<Callback>,
+}
+
+impl CastResolver {
+    pub fn new() -> CastResolver {
+        CastResolver::default()
+    }
+
+    // Add a directory to look for casts in.
+    pub fn search_path<P: Into<PathBuf>>(mut self, path: P) -> CastResolver {
+        self.search_paths.push(path.into());
+        self
+    }
+
+    // Set a function that is asked for the path of a cast before
+    // the directories are searched.
+    pub fn callback<F>(mut self, callback: F) -> CastResolver
+        where F: Fn(&Cast) -> Option<PathBuf> + 'static
+    {
+        self.callback = Some(Box::new(callback));
+        self
+    }
+
+    // Returns the path of the file for 'cast', if it can be found.
+    // 'movie_dir' is the directory of the movie that uses the cast.
+    pub fn resolve(&self, cast: &Cast, movie_dir: Option<&Path>) -> Option<PathBuf> {
+        if let Some(callback) = &self.callback {
+            if let Some(path) = callback(cast) {
+                return Some(path);
+            }
+        }
+
+        let names = candidates(cast);
+
+        movie_dir.into_iter()
+            .chain(self.search_paths.iter().map(|path| path.as_path()))
+            .find_map(|dir| find_file(dir, &names))
+    }
+
+    // Returns a loader that also looks for casts in the directory 'movie_dir'.
+    pub fn in_dir<'a>(&'a self, movie_dir: &'a Path) -> impl CastLoader + 'a {
+        move |cast: &Cast| {
+            self.resolve(cast, Some(movie_dir)).map(DirectorFile::base)
+        }
+    }
+}
+
+// Without the directory of the movie, only the search paths are used.
+impl CastLoader for CastResolver {
+    fn load(&self, cast: &Cast) -> Option<Result<DirectorFile, DirpError>> {
+        self.resolve(cast, None).map(DirectorFile::base)
+    }
+}
+
+// Returns the lowercase file names that the cast could have,
+// in the order they should be tried.
+fn candidates(cast: &Cast) -> Vec<String> {
+    let file_name = file_name(cast.path());
+
+    let stem = match file_name.rfind('.') {
+        Some(i) if i > 0 => &file_name[..i],
+        _ => file_name,
+    };
+
+    // Fall back to the name of the cast if the path is of no use.
+    let stem = if stem.is_empty() {
+        cast.name().as_str()
+    } else {
+        stem
+    };
+
+    let mut names = Vec::new();
+
+    if !file_name.is_empty() {
+        names.push(file_name.to_lowercase());
+    }
+
+    for extension in EXTENSIONS.iter() {
+        let name = format!("{}.{}", stem, extension).to_lowercase();
+
+        if !names.contains(&name) {
+            names.push(name);
+        }
+    }
+
+    names
+}
+
+// The last component of a Mac, Windows or Unix path.
+fn file_name(path: &str) -> &str {
+    path.rsplit(&[':', '\\', '/'][..])
+        .next()
+        .unwrap_or(path)
+}
+
+// Look for a file in 'dir' with one of 'names', ignoring case.
+fn find_file(dir: &Path, names: &[String]) -> Option<PathBuf> {
+    let files: Vec<PathBuf> = fs::read_dir(dir).ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+
+    names.iter().find_map(|name| {
+        files.iter()
+            .find(|path| {
+                path.file_name()
+                    .and_then(|file_name| file_name.to_str())
+                    .is_some_and(|file_name| file_name.to_lowercase() == *name)
+            })
+            .cloned()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    use crate::{test_movie, Warning};
+
+    // An empty directory for a test, which is removed when dropped.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> TempDir {
+            let path = std::env::temp_dir()
+                .join(format!("dirp-{}-{}", name, std::process::id()));
+
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+
+            TempDir(path)
+        }
+
+        fn create(&self, name: &str) -> PathBuf {
+            let path = self.0.join(name);
+            fs::write(&path, b"").unwrap();
+            path
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn mac_and_windows_paths() {
+        let mac = Cast::new("Shared", "Macintosh HD:Movies:Shared.cst");
+        assert_eq!(candidates(&mac), ["shared.cst", "shared.cxt", "shared.cct"]);
+
+        let windows = Cast::new("Shared", "C:\\Movies\\Shared.CXT");
+        assert_eq!(candidates(&windows), ["shared.cxt", "shared.cst", "shared.cct"]);
+    }
+
+    #[test]
+    fn extension_fallback() {
+        // A cast saved as a movie is also looked for as a cast file
+        let cast = Cast::new("Shared", "C:\\Movies\\Shared.dir");
+        assert_eq!(
+            candidates(&cast),
+            ["shared.dir", "shared.cst", "shared.cxt", "shared.cct"]
+        );
+
+        // Without a usable path, the name of the cast is used
+        let cast = Cast::new("Shared", "HD:Movies:");
+        assert_eq!(candidates(&cast), ["shared.cst", "shared.cxt", "shared.cct"]);
+    }
+
+    #[test]
+    fn case_insensitive() {
+        let dir = TempDir::new("case-insensitive");
+        let search_dir = TempDir::new("case-insensitive-search");
+
+        let cast = Cast::new("Shared", "HD:Movies:shared.dir");
+
+        let resolver = CastResolver::new().search_path(&search_dir.0);
+        assert_eq!(resolver.resolve(&cast, Some(&dir.0)), None);
+
+        let found = search_dir.create("SHARED.CXT");
+        assert_eq!(resolver.resolve(&cast, Some(&dir.0)), Some(found));
+
+        // The directory of the movie comes before the search paths
+        let found = dir.create("Shared.Cst");
+        assert_eq!(resolver.resolve(&cast, Some(&dir.0)), Some(found));
+    }
+
+    #[test]
+    fn unresolved_cast() {
+        let dir = TempDir::new("unresolved");
+
+        let movie = test_movie::movie(
+            &[(b"MCsL", test_movie::mcsl(&[("Shared", "HD:Movies:Shared.cst", 1)]))],
+            &[(4, 0x400, b"MCsL")],
+        );
+
+        let resolver = CastResolver::new().search_path(&dir.0);
+        let df = DirectorFile::from_reader(Cursor::new(movie), &resolver).unwrap();
+
+        assert!(df.casts().is_empty());
+        assert!(matches!(
+            &df.warnings()[..],
+            [Warning::UnresolvedCast { name, path }]
+                if name == "Shared" && path == "HD:Movies:Shared.cst"
+        ));
+    }
+}
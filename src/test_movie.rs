@@ -0,0 +1,117 @@
+// Builds small uncompressed big endian movies for tests.
+
+// The data of a chunk, with its FourCC and size.
+fn chunk(fourcc: &[u8; 4], data: &[u8]) -> Vec<u8> {
+    let mut bytes = fourcc.to_vec();
+    bytes.extend(&(data.len() as u32).to_be_bytes());
+    bytes.extend(data);
+
+    // Chunks are padded to an even length
+    if data.len() % 2 == 1 {
+        bytes.push(0);
+    }
+
+    bytes
+}
+
+// A cast list of (name, path, cast library ID) entries.
+pub fn mcsl(entries: &[(&str, &str, u32)]) -> Vec<u8> {
+    let mut data = Vec::new();
+    data.extend(&0u32.to_be_bytes());
+    data.extend(&(entries.len() as u32).to_be_bytes());
+    data.extend(&0u16.to_be_bytes());
+    data.extend(&0u32.to_be_bytes());
+    data.extend(&0u32.to_be_bytes());
+
+    for &(name, path, id) in entries {
+        data.push(name.len() as u8);
+        data.extend(name.as_bytes());
+        data.push(0);
+
+        data.push(path.len() as u8);
+        data.extend(path.as_bytes());
+        data.push(0);
+
+        if !path.is_empty() {
+            data.push(0);
+        }
+
+        // The first and last member, and the member count
+        data.extend(&[1, 1, 0, 0]);
+        data.extend(&id.to_be_bytes());
+    }
+
+    data
+}
+
+// A movie made of 'chunks', which get the resource IDs from 4 up,
+// and a key table of (owned, owner, FourCC) entries.
+pub fn movie(chunks: &[(&[u8; 4], Vec<u8>)], keys: &[(u32, u32, &[u8; 4])]) -> Vec<u8> {
+    let mut key = Vec::new();
+    key.extend(&12u16.to_be_bytes());
+    key.extend(&12u16.to_be_bytes());
+    key.extend(&(keys.len() as u32).to_be_bytes());
+    key.extend(&(keys.len() as u32).to_be_bytes());
+
+    for &(owned, owner, fourcc) in keys {
+        key.extend(&owned.to_be_bytes());
+        key.extend(&owner.to_be_bytes());
+        key.extend(fourcc);
+    }
+
+    let mut chunks: Vec<Vec<u8>> = chunks.iter()
+        .map(|(fourcc, data)| chunk(fourcc, data))
+        .collect();
+    chunks.insert(0, chunk(b"KEY*", &key));
+
+    // The RIFX, imap and mmap chunks come first
+    let count = chunks.len() + 3;
+    let imap_len = 24;
+    let mmap_len = 24 + count * 20;
+
+    let imap_offset = 12;
+    let mmap_offset = imap_offset + 8 + imap_len;
+
+    let mut offset = mmap_offset + 8 + mmap_len;
+    let mut entries = Vec::new();
+
+    for data in &chunks {
+        entries.push((&data[..4], data.len() - 8, offset));
+        offset += data.len();
+    }
+
+    let mut imap = Vec::new();
+    imap.extend(&1u32.to_be_bytes());
+    imap.extend(&(mmap_offset as u32).to_be_bytes());
+    imap.extend(&[0; 16]);
+
+    let mut mmap = Vec::new();
+    mmap.extend(&24u16.to_be_bytes());
+    mmap.extend(&20u16.to_be_bytes());
+    mmap.extend(&(count as u32).to_be_bytes());
+    mmap.extend(&(count as u32).to_be_bytes());
+    mmap.extend(&[0xff; 12]);
+
+    let base: [(&[u8], usize, usize); 3] = [
+        (b"RIFX", offset - 8, 0),
+        (b"imap", imap_len, imap_offset),
+        (b"mmap", mmap_len, mmap_offset),
+    ];
+
+    for &(fourcc, size, offset) in base.iter().chain(entries.iter()) {
+        mmap.extend(fourcc);
+        mmap.extend(&(size as u32).to_be_bytes());
+        mmap.extend(&(offset as u32).to_be_bytes());
+        mmap.extend(&[0; 8]);
+    }
+
+    let mut body = b"MV93".to_vec();
+    body.extend(chunk(b"imap", &imap));
+    body.extend(chunk(b"mmap", &mmap));
+
+    for data in &chunks {
+        body.extend(data);
+    }
+
+    chunk(b"RIFX", &body)
+}
@@ -0,0 +1,130 @@
+use std::fmt;
+
+use crate::DirpError;
+
+use crate::chunk::cast::CastKind;
+
+// A problem found while reading a movie, which didn't stop the rest of it
+// from being read. Warnings are collected on the DirectorFile.
+#[derive(Debug)]
+pub enum Warning {
+    // A chunk of an Afterburner movie with a negative offset,
+    // which was skipped.
+    InvalidChunkOffset {
+        id: u32,
+        offset: i32,
+    },
+    // The file of an external cast could not be found.
+    UnresolvedCast {
+        name: String,
+        path: String,
+    },
+    // An external cast could not be read.
+    UnreadableCast {
+        name: String,
+        error: DirpError,
+    },
+    // The scripts of a cast could not be read.
+    UnreadableScripts {
+        cast: String,
+        error: DirpError,
+    },
+    // A script of a cast that could not be read, which was skipped.
+    UnreadableScript {
+        cast: String,
+        script: u32,
+        error: DirpError,
+    },
+    // A cast member that could not be read, which was skipped.
+    UnreadableMember {
+        cast: String,
+        member: u32,
+        error: DirpError,
+    },
+    // A cast member of a type that isn't decoded.
+    UnsupportedCastKind {
+        cast: String,
+        member: u32,
+        kind: CastKind,
+    },
+    // A bitmap with a bit depth that can't be decoded.
+    UnsupportedBitDepth {
+        cast: String,
+        member: u32,
+        depth: usize,
+    },
+    // The palette of a bitmap could not be found,
+    // so the System Mac palette was used instead.
+    MissingPalette {
+        cast: String,
+        member: u32,
+    },
+    // A bitmap cast member that doesn't own a BITD chunk.
+    MissingBitmapData {
+        cast: String,
+        member: u32,
+    },
+    // A palette cast member that doesn't own a CLUT chunk.
+    MissingPaletteData {
+        cast: String,
+        member: u32,
+    },
+    // The data of a bitmap could not be decoded.
+    UndecodableBitmap {
+        cast: String,
+        member: u32,
+        error: DirpError,
+    },
+    // A general property of a cast member that isn't parsed.
+    UnparsedProperty {
+        cast: String,
+        member: u32,
+        index: usize,
+    },
+}
+
+impl fmt::Display for Warning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Warning::InvalidChunkOffset { id, offset } => {
+                write!(f, "skipped chunk {} with invalid offset {}", id, offset)
+            },
+            Warning::UnresolvedCast { name, path } => {
+                write!(f, "could not find cast {} ({})", name, path)
+            },
+            Warning::UnreadableCast { name, error } => {
+                write!(f, "could not read cast {}: {}", name, error)
+            },
+            Warning::UnreadableScripts { cast, error } => {
+                write!(f, "could not read the scripts of cast {}: {}", cast, error)
+            },
+            Warning::UnreadableScript { cast, script, error } => {
+                write!(f, "could not read script {} of cast {}: {}", script, cast, error)
+            },
+            Warning::UnreadableMember { cast, member, error } => {
+                write!(f, "{} member {}: could not read member: {}", cast, member, error)
+            },
+            Warning::UnsupportedCastKind { cast, member, kind } => {
+                write!(f, "{} member {}: unsupported cast type {:?}", cast, member, kind)
+            },
+            Warning::UnsupportedBitDepth { cast, member, depth } => {
+                write!(f, "{} member {}: unsupported bit depth {}", cast, member, depth)
+            },
+            Warning::MissingPalette { cast, member } => {
+                write!(f, "{} member {}: palette not found", cast, member)
+            },
+            Warning::MissingBitmapData { cast, member } => {
+                write!(f, "{} member {}: bitmap has no BITD chunk", cast, member)
+            },
+            Warning::MissingPaletteData { cast, member } => {
+                write!(f, "{} member {}: palette has no CLUT chunk", cast, member)
+            },
+            Warning::UndecodableBitmap { cast, member, error } => {
+                write!(f, "{} member {}: could not decode bitmap: {}", cast, member, error)
+            },
+            Warning::UnparsedProperty { cast, member, index } => {
+                write!(f, "{} member {}: unparsed property {}", cast, member, index)
+            },
+        }
+    }
+}